@@ -0,0 +1,32 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+/// Generate the C header of the `ffi` module as `bbs_predicate.h` in `OUT_DIR`. The `ffi` tests check that the
+/// committed `include/bbs_predicate.h` is the same, so that building never modifies the source directory.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let config = match cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")) {
+        Ok(config) => config,
+        // The header then differs from the committed one, which the tests report
+        Err(e) => {
+            println!(
+                "cargo:warning=Generating the C header with the default config: {}",
+                e
+            );
+            cbindgen::Config::default()
+        }
+    };
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/ffi.rs"))
+        .generate()
+        .expect("Unable to generate the C header")
+        .write_to_file(out_dir.join("bbs_predicate.h"));
+}
@@ -0,0 +1,202 @@
+#ifndef BBS_PREDICATE_H
+#define BBS_PREDICATE_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of a call. Each variant of `PredicateError` has its own code.
+ */
+typedef enum BbsStatus {
+  BBS_STATUS_OK = 0,
+  /**
+   * A pointer argument is null
+   */
+  BBS_STATUS_NULL_POINTER = 1,
+  /**
+   * The library panicked, which is a bug
+   */
+  BBS_STATUS_PANIC = 2,
+  BBS_STATUS_INVALID_MESSAGE_REF = 10,
+  BBS_STATUS_MISSING_ASSIGNMENT = 11,
+  BBS_STATUS_VALUE_OUT_OF_RANGE = 12,
+  BBS_STATUS_UNSATISFIED_WITNESS = 13,
+  BBS_STATUS_COMMITMENT_SIZE_MISMATCH = 14,
+  BBS_STATUS_INCORRECT_NUMBER_OF_SNARK_PROOFS = 15,
+  BBS_STATUS_PRESENTATION_MISMATCH = 16,
+  BBS_STATUS_INCOMPATIBLE_VERIFYING_KEY = 17,
+  BBS_STATUS_INVALID_KEY_FILE = 18,
+  BBS_STATUS_CIRCUIT_ID_MISMATCH = 19,
+  BBS_STATUS_INVALID_CONTRIBUTION = 20,
+  BBS_STATUS_CEREMONY_PARAMETERS_MISMATCH = 21,
+  BBS_STATUS_INVALID_CRS = 22,
+  BBS_STATUS_NO_SET_PATH = 23,
+  BBS_STATUS_SYNTHESIS_ERROR = 24,
+  BBS_STATUS_LEGO_GROTH16_ERROR = 25,
+  BBS_STATUS_PROOF_SYSTEM_ERROR = 26,
+  BBS_STATUS_BBS_PLUS_ERROR = 27,
+  BBS_STATUS_SERIALIZATION = 28,
+  BBS_STATUS_IO = 29,
+  BBS_STATUS_JSON = 30,
+  BBS_STATUS_CBOR = 31,
+  BBS_STATUS_UNSUPPORTED_BIT_SIZE = 32,
+  BBS_STATUS_COEFFICIENT_OUT_OF_RANGE = 33,
+  BBS_STATUS_INVALID_SET = 34,
+  BBS_STATUS_INVALID_POWERS_OF_TAU = 35,
+} BbsStatus;
+
+/**
+ * Presentation to verify
+ */
+typedef struct BbsPresentation BbsPresentation;
+
+/**
+ * BBS+ public key of an issuer along with the signature params used with it
+ */
+typedef struct BbsPublicKey BbsPublicKey;
+
+/**
+ * LegoGroth16 verifying key of a predicate's circuit
+ */
+typedef struct BbsVerifyingKey BbsVerifyingKey;
+
+/**
+ * Reference to message `message` of credential `credential`
+ */
+typedef struct BbsMessageRef {
+  size_t credential;
+  size_t message;
+} BbsMessageRef;
+
+/**
+ * `min < message < max` where each bound can be inclusive, see `Predicate::Bound`
+ */
+typedef struct BbsBoundCheck {
+  struct BbsMessageRef message;
+  uint64_t min;
+  uint64_t max;
+  /**
+   * Bit-size of the values, 0 to compare them as field elements
+   */
+  size_t bits;
+  bool min_inclusive;
+  bool max_inclusive;
+} BbsBoundCheck;
+
+/**
+ * Sum of `smalls` < sum of `larges`, see `Predicate::SumCompare`
+ */
+typedef struct BbsSumCompare {
+  const struct BbsMessageRef *smalls;
+  size_t smalls_len;
+  const struct BbsMessageRef *larges;
+  size_t larges_len;
+  size_t bits;
+} BbsSumCompare;
+
+/**
+ * Create a verifying key from its serialization. The key must be released with `bbs_verifying_key_free`.
+ *
+ * # Safety
+ *
+ * `bytes` must point to `len` readable bytes and `out` to writable memory for a pointer.
+ */
+enum BbsStatus bbs_verifying_key_from_bytes(const uint8_t *bytes,
+                                            size_t len,
+                                            struct BbsVerifyingKey **out);
+
+/**
+ * Release a verifying key. Does nothing if `key` is null.
+ *
+ * # Safety
+ *
+ * `key` must be null or a key created by `bbs_verifying_key_from_bytes` which has not been released.
+ */
+void bbs_verifying_key_free(struct BbsVerifyingKey *key);
+
+/**
+ * Create an issuer's public key from the serializations of its signature params and public key. The key must be
+ * released with `bbs_public_key_free`.
+ *
+ * # Safety
+ *
+ * `params` and `public_key` must point to `params_len` and `public_key_len` readable bytes and `out` to writable
+ * memory for a pointer.
+ */
+enum BbsStatus bbs_public_key_from_bytes(const uint8_t *params,
+                                         size_t params_len,
+                                         const uint8_t *public_key,
+                                         size_t public_key_len,
+                                         struct BbsPublicKey **out);
+
+/**
+ * Release a public key. Does nothing if `key` is null.
+ *
+ * # Safety
+ *
+ * `key` must be null or a key created by `bbs_public_key_from_bytes` which has not been released.
+ */
+void bbs_public_key_free(struct BbsPublicKey *key);
+
+/**
+ * Create a presentation from its serialization. The presentation must be released with `bbs_presentation_free`.
+ *
+ * # Safety
+ *
+ * `bytes` must point to `len` readable bytes and `out` to writable memory for a pointer.
+ */
+enum BbsStatus bbs_presentation_from_bytes(const uint8_t *bytes,
+                                           size_t len,
+                                           struct BbsPresentation **out);
+
+/**
+ * Release a presentation. Does nothing if `presentation` is null.
+ *
+ * # Safety
+ *
+ * `presentation` must be null or a presentation created by `bbs_presentation_from_bytes` which has not been
+ * released.
+ */
+void bbs_presentation_free(struct BbsPresentation *presentation);
+
+/**
+ * Verify that `presentation` proves the bound check over credentials of `issuers`, given in the order of the
+ * credentials, and was created with the verifier's `nonce`. Returns `Ok` iff the presentation is valid.
+ *
+ * # Safety
+ *
+ * `presentation`, `verifying_key` and `predicate` must be valid pointers, `issuers` must point to
+ * `issuer_count` valid pointers and `nonce` to `nonce_len` readable bytes.
+ */
+enum BbsStatus bbs_verify_bound_check(const struct BbsPresentation *presentation,
+                                      const struct BbsPublicKey *const *issuers,
+                                      size_t issuer_count,
+                                      const struct BbsVerifyingKey *verifying_key,
+                                      const struct BbsBoundCheck *predicate,
+                                      const uint8_t *nonce,
+                                      size_t nonce_len);
+
+/**
+ * Verify that `presentation` proves the sum comparison over credentials of `issuers`, given in the order of the
+ * credentials, and was created with the verifier's `nonce`. Returns `Ok` iff the presentation is valid.
+ *
+ * # Safety
+ *
+ * `presentation`, `verifying_key` and `predicate` must be valid pointers, `issuers` must point to
+ * `issuer_count` valid pointers, `nonce` to `nonce_len` readable bytes and the arrays of `predicate` must have the
+ * given lengths.
+ */
+enum BbsStatus bbs_verify_sum_compare(const struct BbsPresentation *presentation,
+                                      const struct BbsPublicKey *const *issuers,
+                                      size_t issuer_count,
+                                      const struct BbsVerifyingKey *verifying_key,
+                                      const struct BbsSumCompare *predicate,
+                                      const uint8_t *nonce,
+                                      size_t nonce_len);
+
+#endif /* BBS_PREDICATE_H */
@@ -0,0 +1,168 @@
+use crate::bounds::{check_bit_length, check_cmp, enforce_bit_length, enforce_cmp_in_bits};
+use crate::error::PredicateError;
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::cmp::Ordering;
+
+// NOTE: Dates are encoded as the integer YYYYMMDD, eg. 15 August 1990 is 19900815. Adding `n * 10000` to a date
+// gives the same day and month `n` years later, so a person born on `birth_date` is at least `min_age` years old
+// on `today` iff `birth_date + min_age * 10000 <= today`. This needs no knowledge of month lengths or leap years,
+// and a person born on 29 February becomes a year older on 1 March in non-leap years.
+
+/// Upper bound on the bit-size of an encoded date. 99991231 < 2^27.
+pub const DATE_BITS: usize = 27;
+/// Upper bound on the bit-size of the minimum age
+pub const AGE_BITS: usize = 8;
+
+/// Encode a date as YYYYMMDD. Panics if the month or day is out of range.
+pub fn encode_date<F: PrimeField>(year: u32, month: u32, day: u32) -> F {
+    assert!(year <= 9999);
+    assert!((1..=12).contains(&month));
+    assert!((1..=31).contains(&day));
+    F::from((year * 10000 + month * 100 + day) as u64)
+}
+
+/// Enforce that a person born on `birth_date` is at least `min_age` years old on `today`. `birth_date` is
+/// committed and `today` and `min_age` are public inputs, in that order.
+#[derive(Clone)]
+pub struct AgeCheckCircuit<F: Field> {
+    pub birth_date: Option<F>,
+    pub today: Option<F>,
+    pub min_age: Option<F>,
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for AgeCheckCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let birth_date = FpVar::new_variable(
+            cs.clone(),
+            || self.birth_date.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let today = FpVar::new_variable(
+            cs.clone(),
+            || self.today.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+        let min_age = FpVar::new_variable(
+            cs.clone(),
+            || self.min_age.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+
+        enforce_bit_length(&birth_date, DATE_BITS)?;
+        enforce_bit_length(&today, DATE_BITS)?;
+        enforce_bit_length(&min_age, AGE_BITS)?;
+
+        // The date on which the person becomes `min_age` years old. As `min_age * 10000 < 2^22`, it is less
+        // than `2^(DATE_BITS + 1)`.
+        let birthday = birth_date + min_age * ConstraintF::from(10000u64);
+        // birthday <= today
+        enforce_cmp_in_bits(&birthday, &today, Ordering::Less, true, DATE_BITS + 1)
+    }
+}
+
+impl<F: PrimeField> AgeCheckCircuit<F> {
+    /// Check natively that the values satisfy the circuit, in the same order as the circuit
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        let birth_date = self.birth_date.ok_or(PredicateError::MissingAssignment)?;
+        let today = self.today.ok_or(PredicateError::MissingAssignment)?;
+        let min_age = self.min_age.ok_or(PredicateError::MissingAssignment)?;
+        check_bit_length("birth_date", &birth_date, DATE_BITS)?;
+        check_bit_length("today", &today, DATE_BITS)?;
+        check_bit_length("min_age", &min_age, AGE_BITS)?;
+        check_cmp(
+            (
+                "birth_date + min_age * 10000",
+                &(birth_date + min_age * F::from(10000u64)),
+            ),
+            ("today", &today),
+            Ordering::Less,
+            true,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presentation::Predicate;
+    use crate::tests::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn age_check_birth_date_message() {
+        // Prover has a BBS+ signature over his birth date and he wants to prove that he is at least 18 years old
+        // on a date chosen by the verifier without revealing his birth date. Unlike a signed age, the signed
+        // birth date does not go stale.
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        // Birth date is the message at index 3
+        let mut messages = (1..=6u64).map(|i| Fr::from(100 + i)).collect::<Vec<_>>();
+        messages[3] = encode_date(2004, 10, 18);
+        let credentials = [credential(&mut rng, messages)];
+        let age = |today: Fr, min_age: u64| Predicate::Age {
+            birth_date: (0, 3),
+            today,
+            min_age: Fr::from(min_age),
+        };
+
+        // 18th birthday
+        let today = encode_date(2022, 10, 18);
+        let (presentation, vks) =
+            prove_predicates(&mut rng, &credentials, &[age(today, 18)]).unwrap();
+        verify_predicates(&credentials, &[age(today, 18)], &vks, &presentation).unwrap();
+
+        // Presentation is for the given date and minimum age
+        assert!(matches!(
+            verify_predicates(&credentials, &[age(today, 21)], &vks, &presentation),
+            Err(PredicateError::PresentationMismatch)
+        ));
+        // The day before the 18th birthday
+        let predicates = [age(encode_date(2022, 10, 17), 18)];
+        match prove_predicates(&mut rng, &credentials, &predicates) {
+            Err(PredicateError::UnsatisfiedWitness(msg)) => assert_eq!(
+                msg,
+                "birth_date + min_age * 10000 20221018 is not <= today 20221017"
+            ),
+            _ => panic!("expected an unsatisfied witness"),
+        }
+
+        let is_satisfied = |birth: (u32, u32, u32), today: (u32, u32, u32), min_age: u64| {
+            let circuit = AgeCheckCircuit {
+                birth_date: Some(encode_date(birth.0, birth.1, birth.2)),
+                today: Some(encode_date(today.0, today.1, today.2)),
+                min_age: Some(Fr::from(min_age)),
+            };
+            let checked = circuit.check_witness().is_ok();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            let satisfied = cs.is_satisfied().unwrap();
+            assert_eq!(checked, satisfied);
+            satisfied
+        };
+
+        assert!(is_satisfied((2004, 10, 18), (2022, 10, 18), 18));
+        assert!(!is_satisfied((2004, 10, 18), (2022, 10, 17), 18));
+        assert!(is_satisfied((2004, 10, 18), (2023, 1, 1), 18));
+        assert!(!is_satisfied((2004, 10, 18), (2022, 9, 30), 18));
+        // Month boundary, 1 day apart
+        assert!(is_satisfied((2004, 2, 1), (2022, 2, 1), 18));
+        assert!(!is_satisfied((2004, 2, 1), (2022, 1, 31), 18));
+        // Born on 29 February in a leap year, becomes 18 on 1 March in a non-leap year
+        assert!(!is_satisfied((2004, 2, 29), (2022, 2, 28), 18));
+        assert!(is_satisfied((2004, 2, 29), (2022, 3, 1), 18));
+        // and on 29 February in a leap year
+        assert!(!is_satisfied((2000, 2, 29), (2020, 2, 28), 20));
+        assert!(is_satisfied((2000, 2, 29), (2020, 2, 29), 20));
+        assert!(is_satisfied((1950, 1, 1), (2022, 10, 18), 65));
+        assert!(is_satisfied((2022, 10, 18), (2022, 10, 18), 0));
+        // Birth date after today
+        assert!(!is_satisfied((2022, 10, 19), (2022, 10, 18), 0));
+    }
+}
@@ -1,19 +1,289 @@
-use ark_ff::{Field, PrimeField};
+use crate::error::PredicateError;
+use ark_ff::{BigInteger, Field, PrimeField};
 use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
 use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::ns;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use std::cmp::Ordering;
+use ark_std::cmp::Ordering;
+use ark_std::{format, string::String, vec, vec::Vec};
 
-// NOTE: For range check, the following circuits assume that the numbers are of same size as field
-// elements which might not always be true in practice. If the upper bound on the byte-size of the numbers
-// is known, then the no. of constraints in the circuit can be reduced.
+// NOTE: For range check, the following circuits by default assume that the numbers are of same size as field
+// elements which might not always be true in practice. If the upper bound on the bit-size of the numbers
+// is known, it should be passed as `bits` so that the numbers are compared using a bit decomposition of that
+// size which needs far fewer constraints.
 
-/// Enforce min < value < max
+/// Whether a bound excludes or includes the bound itself
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Bound {
+    Strict,
+    Inclusive,
+}
+
+/// The bounds checked by `BoundCheckCircuit`. Only the checked bounds are public inputs, `min` before `max`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundMode {
+    /// Enforce both `min < value` (or `min <= value`) and `value < max` (or `value <= max`)
+    Both { lower: Bound, upper: Bound },
+    /// Enforce only `min < value` (or `min <= value`)
+    LowerOnly(Bound),
+    /// Enforce only `value < max` (or `value <= max`)
+    UpperOnly(Bound),
+}
+
+impl Default for BoundMode {
+    fn default() -> Self {
+        Self::Both {
+            lower: Bound::Strict,
+            upper: Bound::Strict,
+        }
+    }
+}
+
+impl Bound {
+    pub fn is_inclusive(&self) -> bool {
+        *self == Bound::Inclusive
+    }
+}
+
+impl BoundMode {
+    /// Both bounds checked, each strict unless it is inclusive
+    pub fn both(min_inclusive: bool, max_inclusive: bool) -> Self {
+        let bound = |inclusive| {
+            if inclusive {
+                Bound::Inclusive
+            } else {
+                Bound::Strict
+            }
+        };
+        Self::Both {
+            lower: bound(min_inclusive),
+            upper: bound(max_inclusive),
+        }
+    }
+
+    /// The lower bound, if it is checked
+    pub fn lower(&self) -> Option<Bound> {
+        match self {
+            Self::Both { lower, .. } => Some(*lower),
+            Self::LowerOnly(lower) => Some(*lower),
+            Self::UpperOnly(_) => None,
+        }
+    }
+
+    /// The upper bound, if it is checked
+    pub fn upper(&self) -> Option<Bound> {
+        match self {
+            Self::Both { upper, .. } => Some(*upper),
+            Self::LowerOnly(_) => None,
+            Self::UpperOnly(upper) => Some(*upper),
+        }
+    }
+
+    /// Number of public inputs of the circuit, 1 for each checked bound
+    pub fn public_inputs_count(&self) -> usize {
+        self.lower().is_some() as usize + self.upper().is_some() as usize
+    }
+}
+
+/// Enforce that `value` lies within `min` and `max` as per `mode`, by default `min < value < max`. The bound
+/// not checked by `mode` can be `None`.
 #[derive(Clone)]
 pub struct BoundCheckCircuit<F: Field> {
-    min: Option<F>,
-    max: Option<F>,
-    value: Option<F>,
+    pub min: Option<F>,
+    pub max: Option<F>,
+    pub value: Option<F>,
+    /// If set, `value`, `min` and `max` are constrained to be less than `2^bits` and are compared using
+    /// their bit decomposition rather than as full field elements.
+    pub bits: Option<usize>,
+    pub mode: BoundMode,
+}
+
+/// Largest bit-size of the values compared by `enforce_cmp_in_bits`. Bit-sizes come from predicates, which can be
+/// deserialized, so larger ones are errors rather than panics.
+pub fn max_cmp_bits<F: PrimeField>() -> usize {
+    F::size_in_bits() - 2
+}
+
+/// Enforce that `val` fits in `bits` bits, i.e. `0 <= val < 2^bits`, by decomposing it into `bits` boolean
+/// witnesses and checking that they recompose to `val`. Costs `bits + 1` constraints. Fails if `bits` is not
+/// less than the bit-size of the modulus.
+pub fn enforce_bit_length<F: PrimeField>(
+    val: &FpVar<F>,
+    bits: usize,
+) -> Result<(), SynthesisError> {
+    if bits >= F::size_in_bits() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    if val.is_constant() {
+        return if val.value()?.into_repr().num_bits() as usize <= bits {
+            Ok(())
+        } else {
+            Err(SynthesisError::Unsatisfiable)
+        };
+    }
+
+    let cs = val.cs();
+    // No value is available in setup mode
+    let val_bits = match val.value() {
+        Ok(v) => v
+            .into_repr()
+            .to_bits_le()
+            .into_iter()
+            .take(bits)
+            .map(Some)
+            .collect::<Vec<_>>(),
+        Err(_) => vec![None; bits],
+    };
+    let mut bit_vars = Vec::with_capacity(bits);
+    for b in val_bits {
+        bit_vars.push(Boolean::new_witness(cs.clone(), || {
+            b.ok_or(SynthesisError::AssignmentMissing)
+        })?);
+    }
+    Boolean::le_bits_to_fp_var(&bit_vars)?.enforce_equal(val)
+}
+
+/// Same as `FpVar::enforce_cmp` but assumes that both `a` and `b` are less than `2^bits` which the caller
+/// must enforce, eg. using `enforce_bit_length`. Enforces `a < b` when `ordering` is `Ordering::Less` and
+/// `a > b` when `ordering` is `Ordering::Greater`. If `should_also_check_equality` is true, equality is
+/// allowed as well. Costs `bits + 1` constraints. Fails if `bits` is more than `max_cmp_bits`.
+pub fn enforce_cmp_in_bits<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    ordering: Ordering,
+    should_also_check_equality: bool,
+    bits: usize,
+) -> Result<(), SynthesisError> {
+    // The difference below must not be able to wrap around the modulus into `[0, 2^bits)`
+    if bits > max_cmp_bits::<F>() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let (left, right) = match ordering {
+        Ordering::Less => (a, b),
+        Ordering::Greater => (b, a),
+        Ordering::Equal => return a.enforce_equal(b),
+    };
+    // As both `left` and `right` are in `[0, 2^bits)`, `right - left` is in `[0, 2^bits)` iff `left <= right`
+    // and `right - left - 1` is in `[0, 2^bits)` iff `left < right`. Otherwise the difference is negative and
+    // thus a field element close to the modulus.
+    let diff = if should_also_check_equality {
+        right - left
+    } else {
+        right - left - F::one()
+    };
+    enforce_bit_length(&diff, bits)
+}
+
+/// Compare `val` with `bound`, using a bit decomposition of size `bits` if given, after constraining `bound`
+/// to that size. `val` must already be constrained to `bits`.
+fn enforce_cmp_with_bound<F: PrimeField>(
+    val: &FpVar<F>,
+    bound: &FpVar<F>,
+    ordering: Ordering,
+    kind: Bound,
+    bits: Option<usize>,
+) -> Result<(), SynthesisError> {
+    match bits {
+        Some(bits) => {
+            {
+                let _ns = ns!(val.cs(), "range check");
+                enforce_bit_length(bound, bits)?;
+            }
+            let _ns = ns!(val.cs(), "comparison");
+            enforce_cmp_in_bits(val, bound, ordering, kind.is_inclusive(), bits)
+        }
+        None => {
+            let _ns = ns!(val.cs(), "comparison");
+            val.enforce_cmp(bound, ordering, kind.is_inclusive())
+        }
+    }
+}
+
+/// Native counterpart of the bit-size check of `enforce_cmp_in_bits`
+pub(crate) fn check_cmp_bits<F: PrimeField>(bits: usize) -> Result<(), PredicateError> {
+    let max = max_cmp_bits::<F>();
+    if bits > max {
+        return Err(PredicateError::UnsupportedBitSize { bits, max });
+    }
+    Ok(())
+}
+
+/// Native counterpart of `enforce_bit_length` which names `val` in the error
+pub(crate) fn check_bit_length<F: PrimeField>(
+    name: &str,
+    val: &F,
+    bits: usize,
+) -> Result<(), PredicateError> {
+    if val.into_repr().num_bits() as usize > bits {
+        return Err(PredicateError::UnsatisfiedWitness(format!(
+            "{} {} does not fit in {} bits",
+            name,
+            to_decimal(val),
+            bits
+        )));
+    }
+    Ok(())
+}
+
+/// Native counterpart of `enforce_cmp_in_bits` and `FpVar::enforce_cmp` which names `a` and `b` in the error.
+/// Values are compared as integers in `[0, p)`.
+pub(crate) fn check_cmp<F: PrimeField>(
+    (a_name, a): (&str, &F),
+    (b_name, b): (&str, &F),
+    ordering: Ordering,
+    should_also_check_equality: bool,
+) -> Result<(), PredicateError> {
+    let cmp = a.into_repr().cmp(&b.into_repr());
+    if cmp == ordering || (should_also_check_equality && cmp == Ordering::Equal) {
+        return Ok(());
+    }
+    let op = match (ordering, should_also_check_equality) {
+        (Ordering::Less, false) => "<",
+        (Ordering::Less, true) => "<=",
+        (Ordering::Greater, false) => ">",
+        (Ordering::Greater, true) => ">=",
+        (Ordering::Equal, _) => "=",
+    };
+    Err(PredicateError::UnsatisfiedWitness(format!(
+        "{} {} is not {} {} {}",
+        a_name,
+        to_decimal(a),
+        op,
+        b_name,
+        to_decimal(b)
+    )))
+}
+
+/// `val` as an integer in decimal
+pub(crate) fn to_decimal<F: PrimeField>(val: &F) -> String {
+    const TEN_POW_19: u128 = 10_000_000_000_000_000_000;
+    let mut limbs = val.into_repr().as_ref().to_vec();
+    // Digits in base 10^19, least significant first
+    let mut chunks = vec![];
+    while limbs.iter().any(|l| *l != 0) {
+        let mut rem = 0u128;
+        for limb in limbs.iter_mut().rev() {
+            let cur = (rem << 64) | *limb as u128;
+            *limb = (cur / TEN_POW_19) as u64;
+            rem = cur % TEN_POW_19;
+        }
+        chunks.push(rem as u64);
+    }
+    match chunks.split_last() {
+        None => String::from("0"),
+        Some((most, rest)) => {
+            let mut s = format!("{}", most);
+            for c in rest.iter().rev() {
+                s.push_str(&format!("{:019}", c));
+            }
+            s
+        }
+    }
 }
 
 impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
@@ -29,21 +299,73 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
             AllocationMode::Witness,
         )?;
 
-        let min = FpVar::new_variable(
-            cs.clone(),
-            || self.min.ok_or(SynthesisError::AssignmentMissing),
-            AllocationMode::Input,
-        )?;
-        let max = FpVar::new_variable(
-            cs.clone(),
-            || self.max.ok_or(SynthesisError::AssignmentMissing),
-            AllocationMode::Input,
-        )?;
+        if let Some(bits) = self.bits {
+            let _ns = ns!(cs, "range check");
+            enforce_bit_length(&val, bits)?;
+        }
+
+        if let Some(lower) = self.mode.lower() {
+            let _ns = ns!(cs, "lower bound");
+            let min = FpVar::new_variable(
+                cs.clone(),
+                || self.min.ok_or(SynthesisError::AssignmentMissing),
+                AllocationMode::Input,
+            )?;
+            // val greater than min, i.e. val > min and val != min unless the bound is inclusive
+            enforce_cmp_with_bound(&val, &min, Ordering::Greater, lower, self.bits)?;
+        }
 
-        // val strictly less than max, i.e. val < max and val != max
-        val.enforce_cmp(&max, Ordering::Less, false)?;
-        // val strictly greater than max, i.e. val > min and val != min
-        val.enforce_cmp(&min, Ordering::Greater, false)?;
+        if let Some(upper) = self.mode.upper() {
+            let _ns = ns!(cs, "upper bound");
+            let max = FpVar::new_variable(
+                cs.clone(),
+                || self.max.ok_or(SynthesisError::AssignmentMissing),
+                AllocationMode::Input,
+            )?;
+            // val less than max, i.e. val < max and val != max unless the bound is inclusive
+            enforce_cmp_with_bound(&val, &max, Ordering::Less, upper, self.bits)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> BoundCheckCircuit<F> {
+    /// Check natively that `value` satisfies the circuit, to fail with a description of the unsatisfied check
+    /// rather than create a proof that fails verification
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        if let Some(bits) = self.bits {
+            check_cmp_bits::<F>(bits)?;
+        }
+        let value = self.value.ok_or(PredicateError::MissingAssignment)?;
+        let check_size = |name: &str, val: &F| match self.bits {
+            Some(bits) => check_bit_length(name, val, bits),
+            // `FpVar::enforce_cmp` needs both sides to be at most `(p - 1) / 2`
+            None if val.into_repr() > F::modulus_minus_one_div_two() => {
+                Err(PredicateError::UnsatisfiedWitness(format!(
+                    "{} {} is not <= (p - 1) / 2",
+                    name,
+                    to_decimal(val)
+                )))
+            }
+            None => Ok(()),
+        };
+        check_size("value", &value)?;
+        let bounds = [
+            (self.mode.lower(), "min", self.min, Ordering::Greater),
+            (self.mode.upper(), "max", self.max, Ordering::Less),
+        ];
+        for (kind, name, bound, ordering) in bounds {
+            if let Some(kind) = kind {
+                let bound = bound.ok_or(PredicateError::MissingAssignment)?;
+                check_size(name, &bound)?;
+                check_cmp(
+                    ("value", &value),
+                    (name, &bound),
+                    ordering,
+                    kind.is_inclusive(),
+                )?;
+            }
+        }
         Ok(())
     }
 }
@@ -53,9 +375,10 @@ mod tests {
     use super::*;
     use crate::tests::*;
     use ark_bls12_381::Bls12_381;
+    use ark_relations::r1cs::ConstraintSystem;
     use ark_std::{
         collections::{BTreeMap, BTreeSet},
-        rand::{rngs::StdRng, RngCore, SeedableRng},
+        rand::{rngs::StdRng, SeedableRng},
         UniformRand,
     };
     use legogroth16::{
@@ -89,6 +412,8 @@ mod tests {
             min: None,
             max: None,
             value: None,
+            bits: None,
+            mode: BoundMode::default(),
         };
         let params =
             generate_random_parameters::<Bls12_381, _, _>(circuit, commit_witness_count, &mut rng)
@@ -102,7 +427,7 @@ mod tests {
 
         // Message whose bounds need to be proved, i.e. `min < val < max` needs to be proved
         let m_idx = 4;
-        let val = messages[m_idx].clone();
+        let val = messages[m_idx];
 
         let min = Fr::from(100u64);
         let max = Fr::from(107u64);
@@ -111,6 +436,8 @@ mod tests {
             min: Some(min),
             max: Some(max),
             value: Some(val),
+            bits: None,
+            mode: BoundMode::default(),
         };
 
         let start = Instant::now();
@@ -145,7 +472,7 @@ mod tests {
         }));
         statements.add(Statement::PedersenCommitment(PedersenCommitmentStmt {
             bases: bases.clone(),
-            commitment: commitment_to_witness.clone(),
+            commitment: commitment_to_witness,
         }));
 
         let mut meta_statements = MetaStatements::new();
@@ -164,12 +491,7 @@ mod tests {
         let mut witnesses = Witnesses::new();
         witnesses.add(PoKSignatureBBSG1Wit::new_as_witness(
             sig.clone(),
-            messages
-                .clone()
-                .into_iter()
-                .enumerate()
-                .map(|t| t)
-                .collect(),
+            messages.clone().into_iter().enumerate().collect(),
         ));
         witnesses.add(Witness::PedersenCommitment(committed));
 
@@ -190,4 +512,350 @@ mod tests {
         println!("Time taken to verify composite proof {:?}", t2);
         println!("Total time taken to verify proof {:?}", t1 + t2);
     }
+
+    #[test]
+    fn bound_check_with_bits() {
+        // Compare the cost of the circuit treating the value as a full field element with the cost when the value
+        // is known to fit in a certain number of bits as is the case for attributes like age
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let commit_witness_count = 1;
+
+        let min = Fr::from(17u64);
+        let max = Fr::from(150u64);
+        let val = Fr::from(30u64);
+
+        let mut full_width_constraints = 0;
+        for bits in [None, Some(8), Some(32), Some(64)] {
+            let circuit = BoundCheckCircuit {
+                min: Some(min),
+                max: Some(max),
+                value: Some(val),
+                bits,
+                mode: BoundMode::default(),
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            let num_constraints = cs.num_constraints();
+            println!(
+                "Number of constraints for bits {:?} is {}",
+                bits, num_constraints
+            );
+            match bits {
+                None => full_width_constraints = num_constraints,
+                Some(_) => assert!(num_constraints < full_width_constraints),
+            }
+
+            let start = Instant::now();
+            let params = generate_random_parameters::<Bls12_381, _, _>(
+                BoundCheckCircuit::<Fr> {
+                    min: None,
+                    max: None,
+                    value: None,
+                    bits,
+                    mode: BoundMode::default(),
+                },
+                commit_witness_count,
+                &mut rng,
+            )
+            .unwrap();
+            println!(
+                "Time taken for setup for bits {:?} {:?}",
+                bits,
+                start.elapsed()
+            );
+
+            let v = Fr::rand(&mut rng);
+            let start = Instant::now();
+            let snark_proof = create_random_proof(circuit, v, &params, &mut rng).unwrap();
+            println!(
+                "Time taken to create LegoGroth16 proof for bits {:?} {:?}",
+                bits,
+                start.elapsed()
+            );
+
+            let pvk = prepare_verifying_key(&params.vk);
+            verify_proof(&pvk, &snark_proof, &[min, max]).unwrap();
+            verify_witness_commitment(&params.vk, &snark_proof, 2, &[val], &v).unwrap();
+        }
+
+        // Values outside the bounds or not fitting in the given number of bits don't satisfy the circuit
+        for (val, bits) in [
+            (Fr::from(150u64), 8),
+            (Fr::from(17u64), 8),
+            (Fr::from(16u64), 32),
+            (Fr::from(256u64 + 30), 8),
+            (-Fr::from(1u64), 64),
+        ] {
+            let circuit = BoundCheckCircuit {
+                min: Some(min),
+                max: Some(max),
+                value: Some(val),
+                bits: Some(bits),
+                mode: BoundMode::default(),
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn bound_check_modes() {
+        // Check the edge values of each bound mode, with and without a bit decomposition
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+
+        let min = Fr::from(18u64);
+        let max = Fr::from(65u64);
+
+        let is_satisfied = |value: u64, bits: Option<usize>, mode: BoundMode| {
+            let circuit = BoundCheckCircuit {
+                min: mode.lower().map(|_| min),
+                max: mode.upper().map(|_| max),
+                value: Some(Fr::from(value)),
+                bits,
+                mode,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            // 1 for the constant "one" variable
+            assert_eq!(cs.num_instance_variables(), 1 + mode.public_inputs_count());
+            (cs.is_satisfied().unwrap(), cs.num_constraints())
+        };
+
+        use Bound::*;
+        let cases = [
+            (
+                BoundMode::Both {
+                    lower: Strict,
+                    upper: Strict,
+                },
+                vec![(18, false), (19, true), (64, true), (65, false)],
+            ),
+            (
+                BoundMode::Both {
+                    lower: Inclusive,
+                    upper: Inclusive,
+                },
+                vec![(17, false), (18, true), (65, true), (66, false)],
+            ),
+            (
+                BoundMode::Both {
+                    lower: Inclusive,
+                    upper: Strict,
+                },
+                vec![(17, false), (18, true), (64, true), (65, false)],
+            ),
+            (
+                BoundMode::Both {
+                    lower: Strict,
+                    upper: Inclusive,
+                },
+                vec![(18, false), (19, true), (65, true), (66, false)],
+            ),
+            (
+                BoundMode::LowerOnly(Strict),
+                vec![(18, false), (19, true), (200, true)],
+            ),
+            (
+                BoundMode::LowerOnly(Inclusive),
+                vec![(0, false), (17, false), (18, true), (200, true)],
+            ),
+            (
+                BoundMode::UpperOnly(Strict),
+                vec![(0, true), (64, true), (65, false)],
+            ),
+            (
+                BoundMode::UpperOnly(Inclusive),
+                vec![(0, true), (65, true), (66, false), (200, false)],
+            ),
+        ];
+
+        for bits in [None, Some(8)] {
+            let (_, both_sided_constraints) = is_satisfied(30, bits, BoundMode::default());
+            for (mode, values) in cases.iter() {
+                for (value, expected) in values {
+                    let (satisfied, num_constraints) = is_satisfied(*value, bits, *mode);
+                    assert_eq!(
+                        satisfied, *expected,
+                        "value {} with mode {:?} and bits {:?}",
+                        value, mode, bits
+                    );
+                    if mode.public_inputs_count() == 1 {
+                        assert!(num_constraints < both_sided_constraints);
+                    }
+                }
+            }
+        }
+
+        // A one-sided predicate like age >= 18 needs only 1 public input
+        let mode = BoundMode::LowerOnly(Inclusive);
+        let params = generate_random_parameters::<Bls12_381, _, _>(
+            BoundCheckCircuit::<Fr> {
+                min: None,
+                max: None,
+                value: None,
+                bits: Some(8),
+                mode,
+            },
+            1,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(params.vk.gamma_abc_g1.len(), 1 + 1 + 1);
+
+        let val = Fr::from(18u64);
+        let v = Fr::rand(&mut rng);
+        let circuit = BoundCheckCircuit {
+            min: Some(min),
+            max: None,
+            value: Some(val),
+            bits: Some(8),
+            mode,
+        };
+        let snark_proof = create_random_proof(circuit, v, &params, &mut rng).unwrap();
+        verify_proof(&prepare_verifying_key(&params.vk), &snark_proof, &[min]).unwrap();
+        verify_witness_commitment(&params.vk, &snark_proof, 1, &[val], &v).unwrap();
+    }
+
+    #[test]
+    fn check_witness_messages() {
+        // `check_witness` fails exactly when the circuit is not satisfied, describing the 1st unsatisfied check
+
+        let check = |value: Fr, bits: Option<usize>, mode: BoundMode| {
+            let circuit = BoundCheckCircuit {
+                min: Some(Fr::from(100u64)),
+                max: Some(Fr::from(107u64)),
+                value: Some(value),
+                bits,
+                mode,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone()).unwrap();
+            let result = circuit.check_witness();
+            assert_eq!(result.is_ok(), cs.is_satisfied().unwrap());
+            match result {
+                Ok(()) => None,
+                Err(PredicateError::UnsatisfiedWitness(e)) => Some(e),
+                Err(e) => panic!("unexpected {:?}", e),
+            }
+        };
+
+        let inclusive = BoundMode::Both {
+            lower: Bound::Inclusive,
+            upper: Bound::Inclusive,
+        };
+        let cases = [
+            (105, Some(16), BoundMode::default(), None),
+            (
+                107,
+                Some(16),
+                BoundMode::default(),
+                Some("value 107 is not < max 107"),
+            ),
+            (
+                100,
+                None,
+                BoundMode::default(),
+                Some("value 100 is not > min 100"),
+            ),
+            (107, None, inclusive, None),
+            (
+                108,
+                Some(16),
+                inclusive,
+                Some("value 108 is not <= max 107"),
+            ),
+            (99, None, inclusive, Some("value 99 is not >= min 100")),
+            (50, Some(8), BoundMode::UpperOnly(Bound::Strict), None),
+            (
+                300,
+                Some(8),
+                BoundMode::default(),
+                Some("value 300 does not fit in 8 bits"),
+            ),
+            (
+                50,
+                Some(6),
+                BoundMode::default(),
+                Some("min 100 does not fit in 6 bits"),
+            ),
+        ];
+        for (value, bits, mode, expected) in cases {
+            assert_eq!(
+                check(Fr::from(value as u64), bits, mode).as_deref(),
+                expected
+            );
+        }
+        // -1 is p - 1
+        assert_eq!(
+            check(-Fr::from(1u64), None, BoundMode::UpperOnly(Bound::Strict)).as_deref(),
+            Some("value 52435875175126190479447740508185965837690552500527637822603658699938581184512 is not <= (p - 1) / 2")
+        );
+
+        let circuit = BoundCheckCircuit::<Fr> {
+            min: Some(Fr::from(100u64)),
+            max: None,
+            value: Some(Fr::from(105u64)),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        assert!(matches!(
+            circuit.check_witness(),
+            Err(PredicateError::MissingAssignment)
+        ));
+
+        assert_eq!(to_decimal(&Fr::from(0u64)), "0");
+        assert_eq!(
+            to_decimal(&Fr::from(10_000_000_000_000_000_000u128)),
+            "10000000000000000000"
+        );
+        assert_eq!(
+            to_decimal(&(Fr::from(u64::MAX) * Fr::from(u64::MAX))),
+            "340282366920938463426481119284349108225"
+        );
+    }
+
+    #[test]
+    fn unsupported_bits() {
+        // `bits` comes from the predicate so a too large one is an error, both when synthesizing and checking
+        let max = max_cmp_bits::<Fr>();
+        assert_eq!(max, 253);
+        for (bits, supported) in [
+            (max, true),
+            (max + 1, false),
+            (255, false),
+            (usize::MAX, false),
+        ] {
+            let circuit = BoundCheckCircuit {
+                min: Some(Fr::from(100u64)),
+                max: Some(Fr::from(107u64)),
+                value: Some(Fr::from(105u64)),
+                bits: Some(bits),
+                mode: BoundMode::default(),
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let result = circuit.clone().generate_constraints(cs.clone());
+            if supported {
+                result.unwrap();
+                assert!(cs.is_satisfied().unwrap());
+                circuit.check_witness().unwrap();
+            } else {
+                assert!(matches!(result, Err(SynthesisError::Unsatisfiable)));
+                assert!(matches!(
+                    circuit.check_witness(),
+                    Err(PredicateError::UnsupportedBitSize { bits: b, max: 253 }) if b == bits
+                ));
+            }
+        }
+
+        let val = FpVar::Constant(Fr::from(1u64));
+        assert!(matches!(
+            enforce_bit_length(&val, 255),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+        enforce_bit_length(&val, 254).unwrap();
+    }
 }
@@ -0,0 +1,849 @@
+use crate::error::PredicateError;
+use crate::Fr;
+use ark_bls12_381::{Bls12_381, Fq, Fq2, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::msm::VariableBaseMSM;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use ark_std::{vec, vec::Vec, UniformRand};
+use blake2::{Blake2b, Digest};
+use legogroth16::{generate_random_parameters, ProvingKey};
+
+// NOTE: This is phase 2 of the setup of "Scalable Multi-party Computation for zk-SNARK Parameters in the Random
+// Beacon Model" by Bowe, Gabizon and Miers, adapted to LegoGroth16. The ceremony starts from parameters of the
+// circuit whose circuit independent trapdoors (`tau`, `alpha` and `beta`) come from a phase 1 ceremony, the
+// powers of tau, and whose `gamma`, `delta` and `eta` are 1. Each participant then multiplies the trapdoors `delta`
+// and `eta` by secrets `delta'` and `eta'`, i.e. `delta_g1`, `delta_g2` and `eta_gamma_inv_g1` are multiplied by
+// `delta'`, `delta'` and `eta'`, `eta_delta_inv_g1` by `eta' / delta'` and `h_query` and `l_query` by `1 / delta'`.
+// Participants prove knowledge of their secrets so that they cannot cancel earlier contributions, and the final
+// parameters are secure if a single participant has forgotten their secrets.
+//
+// `PowersOfTau::params` derives the initial parameters by evaluating the QAP of the circuit at `tau` in the
+// exponent: an inverse FFT turns the powers of tau into the Lagrange basis of the QAP's domain, and `h_query[i]`
+// is `tau^(m + i) - tau^i` for a domain of size `m`. Contributions cannot remove trapdoors known to whoever
+// created the initial parameters, so the powers must be the final ones of a verified phase 1 ceremony, and
+// parameters from eg. `generate_random_parameters` are only fit for tests.
+//
+// The transcript is bound by a hash chain starting with the hash of the initial parameters, each contribution
+// being hashed with the hash before it. The proof of knowledge of a secret `x` is `(s, s * x, r * x)` for a random
+// `s` in G1 and `r` in G2 derived from the hash before the contribution, `s` and `s * x`. `r` is found by
+// try-and-increment: Blake2b of `HASH_TO_G2_LABEL`, the input and a counter gives an x-coordinate and the sign of
+// the y-coordinate, the counter being incremented until they are those of a point on the curve, which is then
+// multiplied by the cofactor. So `r` has an unknown discrete logarithm and depends only on Blake2b, unlike a point
+// sampled by a seeded RNG whose stream can change between versions of the RNG. Changing the derivation changes
+// every transcript hash and needs a new label.
+
+/// Domain separator for the hash of the initial parameters
+const CEREMONY_LABEL: &[u8] = b"BBS-predicate-ceremony";
+
+/// Domain separator and version of the derivation of points in G2 by `hash_to_g2`
+const HASH_TO_G2_LABEL: &[u8] = b"BBS-predicate-ceremony-hash-to-G2-v1";
+
+/// Public record of a participant's contribution
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contribution {
+    /// `delta_g1` after the contribution
+    pub delta_after: G1Affine,
+    /// `eta_gamma_inv_g1` after the contribution
+    pub eta_gamma_inv_after: G1Affine,
+    pub s: G1Affine,
+    /// `s * delta'`
+    pub s_delta: G1Affine,
+    /// `s * eta'`
+    pub s_eta: G1Affine,
+    /// `r * delta'`
+    pub r_delta: G2Affine,
+    /// `r * eta'`
+    pub r_eta: G2Affine,
+}
+
+/// Parameters of a circuit along with the contributions made to them so far. This is passed from one participant
+/// to the next.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Ceremony {
+    params: ProvingKey<Bls12_381>,
+    initial_hash: Vec<u8>,
+    contributions: Vec<Contribution>,
+}
+
+/// Output of phase 1 for circuits whose QAP domain has at most `size` elements, all as multiples of the standard
+/// generators. `check` only checks that the elements are well-formed, not that `tau`, `alpha` and `beta` are
+/// unknown, which needs the powers to be those of a verified phase 1 transcript.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTau {
+    /// `tau^i` in G1 for `i < 2 * size - 1`
+    pub tau_g1: Vec<G1Affine>,
+    /// `tau^i` in G2 for `i < size`
+    pub tau_g2: Vec<G2Affine>,
+    /// `alpha * tau^i` in G1 for `i < size`
+    pub alpha_tau_g1: Vec<G1Affine>,
+    /// `beta * tau^i` in G1 for `i < size`
+    pub beta_tau_g1: Vec<G1Affine>,
+    pub beta_g2: G2Affine,
+}
+
+impl PowersOfTau {
+    /// Largest QAP domain the powers can be used for
+    pub fn size(&self) -> usize {
+        self.tau_g2.len()
+    }
+
+    /// Check that the elements are powers of the same `tau` multiplied by the same `alpha` and `beta`, starting
+    /// from the standard generators. Fails with `InvalidPowersOfTau` naming the first malformed field.
+    pub fn check<R: RngCore>(&self, rng: &mut R) -> Result<(), PredicateError> {
+        let invalid = |field| Err(PredicateError::InvalidPowersOfTau(field));
+        let size = self.size();
+        if size < 2 {
+            return invalid("tau_g2");
+        }
+        if self.tau_g1.len() != 2 * size - 1 {
+            return invalid("tau_g1");
+        }
+        if self.alpha_tau_g1.len() != size {
+            return invalid("alpha_tau_g1");
+        }
+        if self.beta_tau_g1.len() != size {
+            return invalid("beta_tau_g1");
+        }
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        if self.tau_g1[0] != g1 || self.tau_g1[1].is_zero() {
+            return invalid("tau_g1");
+        }
+        if self.tau_g2[0] != g2 {
+            return invalid("tau_g2");
+        }
+        if self.alpha_tau_g1[0].is_zero() {
+            return invalid("alpha_tau_g1");
+        }
+        if self.beta_tau_g1[0].is_zero() {
+            return invalid("beta_tau_g1");
+        }
+        // Consecutive elements differ by `tau`, the same in G1 and G2
+        let tau = (g2, self.tau_g2[1]);
+        let n = self.tau_g1.len();
+        if !same_ratio(merge(&self.tau_g1[..n - 1], &self.tau_g1[1..], rng), tau) {
+            return invalid("tau_g1");
+        }
+        if !same_ratio(
+            (g1, self.tau_g1[1]),
+            merge(&self.tau_g2[..size - 1], &self.tau_g2[1..], rng),
+        ) {
+            return invalid("tau_g2");
+        }
+        if !same_ratio(
+            merge(&self.alpha_tau_g1[..size - 1], &self.alpha_tau_g1[1..], rng),
+            tau,
+        ) {
+            return invalid("alpha_tau_g1");
+        }
+        if !same_ratio(
+            merge(&self.beta_tau_g1[..size - 1], &self.beta_tau_g1[1..], rng),
+            tau,
+        ) {
+            return invalid("beta_tau_g1");
+        }
+        if !same_ratio((g1, self.beta_tau_g1[0]), (g2, self.beta_g2)) {
+            return invalid("beta_g2");
+        }
+        Ok(())
+    }
+
+    /// Initial parameters of the circuit for `Ceremony::new`. They are derived deterministically, so anyone can
+    /// derive them from the same powers to verify the ceremony with `Ceremony::verify`. Fails with
+    /// `InvalidPowersOfTau("tau_g2")` if the circuit's QAP domain is larger than `size`.
+    pub fn params<C: ConstraintSynthesizer<Fr> + Clone>(
+        &self,
+        circuit: C,
+        commit_witness_count: usize,
+    ) -> Result<ProvingKey<Bls12_381>, PredicateError> {
+        // Synthesize the circuit as `generate_random_parameters` does
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.clone().generate_constraints(cs.clone())?;
+        cs.finalize();
+        let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+        let num_instance = matrices.num_instance_variables;
+        let num_constraints = matrices.num_constraints;
+        if matrices.num_witness_variables < commit_witness_count {
+            return Err(PredicateError::CommitmentSizeMismatch {
+                expected: commit_witness_count,
+                found: matrices.num_witness_variables,
+            });
+        }
+        // The public inputs are enforced by constraints `input * 0 = 0` after the circuit's constraints
+        let domain = GeneralEvaluationDomain::<Fr>::new(num_constraints + num_instance)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let m = domain.size();
+        if m > self.size() || self.tau_g1.len() < 2 * m - 1 {
+            return Err(PredicateError::InvalidPowersOfTau("tau_g2"));
+        }
+
+        // `L_j(tau)` for the Lagrange polynomials `L_j` of the domain
+        let lagrange_g1 = |powers: &[G1Affine]| {
+            domain.ifft(
+                &powers[..m]
+                    .iter()
+                    .map(|p| p.into_projective())
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let u = lagrange_g1(&self.tau_g1);
+        let alpha_u = lagrange_g1(&self.alpha_tau_g1);
+        let beta_u = lagrange_g1(&self.beta_tau_g1);
+        let u_g2 = domain.ifft(
+            &self.tau_g2[..m]
+                .iter()
+                .map(|p| p.into_projective())
+                .collect::<Vec<_>>(),
+        );
+
+        // `A_k(tau)`, `B_k(tau)` and `beta * A_k(tau) + alpha * B_k(tau) + C_k(tau)` for each variable `k`
+        let num_variables = num_instance + matrices.num_witness_variables;
+        let mut a = vec![G1Projective::zero(); num_variables];
+        let mut b_g1 = vec![G1Projective::zero(); num_variables];
+        let mut b_g2 = vec![G2Projective::zero(); num_variables];
+        let mut abc = vec![G1Projective::zero(); num_variables];
+        let inputs = num_constraints..num_constraints + num_instance;
+        a[..num_instance].copy_from_slice(&u[inputs.clone()]);
+        abc[..num_instance].copy_from_slice(&beta_u[inputs]);
+        for (j, ((row_a, row_b), row_c)) in matrices
+            .a
+            .iter()
+            .zip(&matrices.b)
+            .zip(&matrices.c)
+            .enumerate()
+        {
+            for (coeff, k) in row_a {
+                a[*k] += times(u[j], coeff);
+                abc[*k] += times(beta_u[j], coeff);
+            }
+            for (coeff, k) in row_b {
+                b_g1[*k] += times(u[j], coeff);
+                b_g2[*k] += times(u_g2[j], coeff);
+                abc[*k] += times(alpha_u[j], coeff);
+            }
+            for (coeff, k) in row_c {
+                abc[*k] += times(u[j], coeff);
+            }
+        }
+        // `(tau^m - 1) * tau^i`, the vanishing polynomial of the domain times the powers of tau
+        let h = (0..m - 1)
+            .map(|i| self.tau_g1[m + i].into_projective() - self.tau_g1[i].into_projective())
+            .collect::<Vec<_>>();
+
+        // Only the layout of the key is taken from `generate_random_parameters`, every element is replaced
+        let mut params = generate_random_parameters::<Bls12_381, _, _>(
+            circuit,
+            commit_witness_count,
+            &mut StdRng::seed_from_u64(0u64),
+        )?;
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        let n = num_instance + commit_witness_count;
+        params.vk.alpha_g1 = self.alpha_tau_g1[0];
+        params.vk.beta_g2 = self.beta_g2;
+        params.vk.gamma_g2 = g2;
+        params.vk.delta_g2 = g2;
+        params.vk.gamma_abc_g1 = G1Projective::batch_normalization_into_affine(&abc[..n]);
+        params.vk.eta_gamma_inv_g1 = g1;
+        params.beta_g1 = self.beta_tau_g1[0];
+        params.delta_g1 = g1;
+        params.eta_delta_inv_g1 = g1;
+        params.a_query = G1Projective::batch_normalization_into_affine(&a);
+        params.b_g1_query = G1Projective::batch_normalization_into_affine(&b_g1);
+        params.b_g2_query = G2Projective::batch_normalization_into_affine(&b_g2);
+        params.h_query = G1Projective::batch_normalization_into_affine(&h);
+        params.l_query = G1Projective::batch_normalization_into_affine(&abc[n..]);
+        Ok(params)
+    }
+}
+
+impl Ceremony {
+    /// Start the ceremony from the parameters of the circuit derived by `PowersOfTau::params` from the output of
+    /// a verified phase 1 ceremony. Other parameters have trapdoors known to whoever created them, which the
+    /// contributions do not remove.
+    pub fn new(params: ProvingKey<Bls12_381>) -> Result<Self, PredicateError> {
+        let initial_hash = initial_hash(&params)?;
+        Ok(Self {
+            params,
+            initial_hash,
+            contributions: Vec::new(),
+        })
+    }
+
+    /// Check `powers` and start the ceremony from the parameters of the circuit derived from them
+    pub fn from_powers_of_tau<C: ConstraintSynthesizer<Fr> + Clone, R: RngCore>(
+        powers: &PowersOfTau,
+        circuit: C,
+        commit_witness_count: usize,
+        rng: &mut R,
+    ) -> Result<Self, PredicateError> {
+        powers.check(rng)?;
+        Self::new(powers.params(circuit, commit_witness_count)?)
+    }
+
+    /// Randomize the parameters with new secrets which are discarded once this returns. Returns the hash of the
+    /// transcript after the contribution so that the participant can check that it is part of the final
+    /// transcript.
+    pub fn contribute<R: RngCore>(&mut self, rng: &mut R) -> Result<Vec<u8>, PredicateError> {
+        let delta = non_zero(rng);
+        let eta = non_zero(rng);
+        let delta_inv = delta.inverse().unwrap();
+
+        let s = G1Projective::rand(rng).into_affine();
+        let s_delta = s.mul(delta.into_repr()).into_affine();
+        let s_eta = s.mul(eta.into_repr()).into_affine();
+        let r = hash_to_g2(self.transcript_hash()?, &s, &s_delta, &s_eta)?;
+
+        self.params.delta_g1 = self.params.delta_g1.mul(delta.into_repr()).into_affine();
+        self.params.vk.delta_g2 = self.params.vk.delta_g2.mul(delta.into_repr()).into_affine();
+        self.params.vk.eta_gamma_inv_g1 = self
+            .params
+            .vk
+            .eta_gamma_inv_g1
+            .mul(eta.into_repr())
+            .into_affine();
+        self.params.eta_delta_inv_g1 = self
+            .params
+            .eta_delta_inv_g1
+            .mul((eta * delta_inv).into_repr())
+            .into_affine();
+        self.params.h_query = scale(&self.params.h_query, delta_inv);
+        self.params.l_query = scale(&self.params.l_query, delta_inv);
+
+        self.contributions.push(Contribution {
+            delta_after: self.params.delta_g1,
+            eta_gamma_inv_after: self.params.vk.eta_gamma_inv_g1,
+            s,
+            s_delta,
+            s_eta,
+            r_delta: r.mul(delta.into_repr()).into_affine(),
+            r_eta: r.mul(eta.into_repr()).into_affine(),
+        });
+        self.transcript_hash()
+    }
+
+    /// Check that `after` is `before` with exactly 1 more valid contribution and parameters updated by it. Returns
+    /// the hash of the transcript after the contribution.
+    pub fn verify_contribution<R: RngCore>(
+        before: &Self,
+        after: &Self,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, PredicateError> {
+        let index = before.contributions.len();
+        if before.initial_hash != after.initial_hash
+            || after.contributions.len() != index + 1
+            || after.contributions[..index] != before.contributions[..]
+        {
+            return Err(PredicateError::CeremonyParametersMismatch);
+        }
+        let contribution = &after.contributions[index];
+        verify_contribution(
+            &before.transcript_hash()?,
+            &before.params,
+            contribution,
+            index,
+        )?;
+        if after.params.delta_g1 != contribution.delta_after
+            || after.params.vk.eta_gamma_inv_g1 != contribution.eta_gamma_inv_after
+        {
+            return Err(PredicateError::CeremonyParametersMismatch);
+        }
+        verify_params(&before.params, &after.params, rng)?;
+        after.transcript_hash()
+    }
+
+    /// Check the whole transcript, i.e. that the parameters are derived from `initial` by the valid contributions
+    /// in the transcript. Returns the hash of the transcript after each contribution.
+    pub fn verify<R: RngCore>(
+        &self,
+        initial: &ProvingKey<Bls12_381>,
+        rng: &mut R,
+    ) -> Result<Vec<Vec<u8>>, PredicateError> {
+        let mut hash = initial_hash(initial)?;
+        if hash != self.initial_hash {
+            return Err(PredicateError::CeremonyParametersMismatch);
+        }
+        let mut params = initial.clone();
+        let mut hashes = Vec::with_capacity(self.contributions.len());
+        for (index, contribution) in self.contributions.iter().enumerate() {
+            verify_contribution(&hash, &params, contribution, index)?;
+            // Only the elements checked by the contribution are updated, the others are checked once at the end
+            params.delta_g1 = contribution.delta_after;
+            params.vk.eta_gamma_inv_g1 = contribution.eta_gamma_inv_after;
+            hash = next_hash(&hash, contribution)?;
+            hashes.push(hash.clone());
+        }
+        if params.delta_g1 != self.params.delta_g1
+            || params.vk.eta_gamma_inv_g1 != self.params.vk.eta_gamma_inv_g1
+        {
+            return Err(PredicateError::CeremonyParametersMismatch);
+        }
+        verify_params(initial, &self.params, rng)?;
+        Ok(hashes)
+    }
+
+    /// Contributions made so far
+    pub fn contributions(&self) -> &[Contribution] {
+        &self.contributions
+    }
+
+    /// Current parameters, to be used with `create_random_proof` and `verify_proof` once the ceremony is over
+    pub fn params(&self) -> &ProvingKey<Bls12_381> {
+        &self.params
+    }
+
+    pub fn into_params(self) -> ProvingKey<Bls12_381> {
+        self.params
+    }
+
+    /// Hash of the transcript so far
+    pub fn transcript_hash(&self) -> Result<Vec<u8>, PredicateError> {
+        let mut hash = self.initial_hash.clone();
+        for contribution in &self.contributions {
+            hash = next_hash(&hash, contribution)?;
+        }
+        Ok(hash)
+    }
+}
+
+/// Check the proofs of knowledge in `contribution` and that it was made to parameters with `delta_g1` and
+/// `eta_gamma_inv_g1` of `before`
+fn verify_contribution(
+    hash: &[u8],
+    before: &ProvingKey<Bls12_381>,
+    contribution: &Contribution,
+    index: usize,
+) -> Result<(), PredicateError> {
+    let invalid = || PredicateError::InvalidContribution(index);
+    if contribution.s.is_zero()
+        || contribution.delta_after.is_zero()
+        || contribution.eta_gamma_inv_after.is_zero()
+    {
+        return Err(invalid());
+    }
+    let r = hash_to_g2(
+        hash.to_vec(),
+        &contribution.s,
+        &contribution.s_delta,
+        &contribution.s_eta,
+    )
+    .map_err(|_| invalid())?;
+    let valid = same_ratio(
+        (contribution.s, contribution.s_delta),
+        (r, contribution.r_delta),
+    ) && same_ratio(
+        (contribution.s, contribution.s_eta),
+        (r, contribution.r_eta),
+    ) && same_ratio(
+        (before.delta_g1, contribution.delta_after),
+        (r, contribution.r_delta),
+    ) && same_ratio(
+        (before.vk.eta_gamma_inv_g1, contribution.eta_gamma_inv_after),
+        (r, contribution.r_eta),
+    );
+    if !valid {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// Check that `after` differs from `before` only in `delta` and `eta` and that the elements depending on them are
+/// consistent with `delta_g1` and `eta_gamma_inv_g1` of `after`
+fn verify_params<R: RngCore>(
+    before: &ProvingKey<Bls12_381>,
+    after: &ProvingKey<Bls12_381>,
+    rng: &mut R,
+) -> Result<(), PredicateError> {
+    let unchanged = before.vk.alpha_g1 == after.vk.alpha_g1
+        && before.vk.beta_g2 == after.vk.beta_g2
+        && before.vk.gamma_g2 == after.vk.gamma_g2
+        && before.vk.gamma_abc_g1 == after.vk.gamma_abc_g1
+        && before.beta_g1 == after.beta_g1
+        && before.a_query == after.a_query
+        && before.b_g1_query == after.b_g1_query
+        && before.b_g2_query == after.b_g2_query
+        && before.h_query.len() == after.h_query.len()
+        && before.l_query.len() == after.l_query.len();
+    if !unchanged {
+        return Err(PredicateError::CeremonyParametersMismatch);
+    }
+    let consistent = !after.delta_g1.is_zero()
+        // `delta_g1` and `delta_g2` are multiplied by the same value, so they keep having the same discrete
+        // logarithm. Unlike comparing them to the generators, this also holds for parameters whose generators
+        // are not the standard ones.
+        && same_ratio(
+            (before.delta_g1, after.delta_g1),
+            (before.vk.delta_g2, after.vk.delta_g2),
+        )
+        // `eta_delta_inv_g1 * delta = eta_gamma_inv_g1 * gamma`
+        && Bls12_381::pairing(after.eta_delta_inv_g1, after.vk.delta_g2)
+            == Bls12_381::pairing(after.vk.eta_gamma_inv_g1, after.vk.gamma_g2)
+        // `h_query` and `l_query` are divided by the same value `delta_g2` is multiplied by
+        && same_ratio(
+            merge(&after.h_query, &before.h_query, rng),
+            (before.vk.delta_g2, after.vk.delta_g2),
+        )
+        && same_ratio(
+            merge(&after.l_query, &before.l_query, rng),
+            (before.vk.delta_g2, after.vk.delta_g2),
+        );
+    if !consistent {
+        return Err(PredicateError::CeremonyParametersMismatch);
+    }
+    Ok(())
+}
+
+/// Whether `g1.1 = g1.0 * x` and `g2.1 = g2.0 * x` for some `x`
+pub(crate) fn same_ratio(g1: (G1Affine, G1Affine), g2: (G2Affine, G2Affine)) -> bool {
+    Bls12_381::pairing(g1.0, g2.1) == Bls12_381::pairing(g1.1, g2.0)
+}
+
+/// Combine `a` and `b` with the same random coefficients so that `same_ratio` on the results checks that
+/// `b[i] = a[i] * x` for all `i` with the same `x`, except with negligible probability
+fn merge<G: AffineCurve<ScalarField = Fr>, R: RngCore>(a: &[G], b: &[G], rng: &mut R) -> (G, G) {
+    let coeffs = (0..a.len())
+        .map(|_| Fr::rand(rng).into_repr())
+        .collect::<Vec<_>>();
+    (
+        VariableBaseMSM::multi_scalar_mul(a, &coeffs).into_affine(),
+        VariableBaseMSM::multi_scalar_mul(b, &coeffs).into_affine(),
+    )
+}
+
+fn times<G: ProjectiveCurve<ScalarField = Fr>>(base: G, coeff: &Fr) -> G {
+    if coeff.is_one() {
+        base
+    } else {
+        base.mul(coeff.into_repr())
+    }
+}
+
+fn scale(bases: &[G1Affine], by: Fr) -> Vec<G1Affine> {
+    let scaled = bases
+        .iter()
+        .map(|b| b.mul(by.into_repr()))
+        .collect::<Vec<_>>();
+    G1Projective::batch_normalization_into_affine(&scaled)
+}
+
+fn non_zero<R: RngCore>(rng: &mut R) -> Fr {
+    loop {
+        let x = Fr::rand(rng);
+        if !x.is_zero() {
+            return x;
+        }
+    }
+}
+
+fn initial_hash(params: &ProvingKey<Bls12_381>) -> Result<Vec<u8>, PredicateError> {
+    let mut bytes = CEREMONY_LABEL.to_vec();
+    params.serialize(&mut bytes)?;
+    Ok(Blake2b::digest(&bytes).to_vec())
+}
+
+fn next_hash(hash: &[u8], contribution: &Contribution) -> Result<Vec<u8>, PredicateError> {
+    let mut bytes = hash.to_vec();
+    contribution.serialize(&mut bytes)?;
+    Ok(Blake2b::digest(&bytes).to_vec())
+}
+
+/// Point in G2 with an unknown discrete logarithm derived from the transcript hash and the points of a proof of
+/// knowledge by try-and-increment
+fn hash_to_g2(
+    mut bytes: Vec<u8>,
+    s: &G1Affine,
+    s_delta: &G1Affine,
+    s_eta: &G1Affine,
+) -> Result<G2Affine, PredicateError> {
+    s.serialize(&mut bytes)?;
+    s_delta.serialize(&mut bytes)?;
+    s_eta.serialize(&mut bytes)?;
+    let digest = |counter: u64, index: u8| {
+        let mut hasher = Blake2b::new();
+        hasher.update(HASH_TO_G2_LABEL);
+        hasher.update(&bytes);
+        hasher.update(counter.to_le_bytes());
+        hasher.update([index]);
+        hasher.finalize()
+    };
+    let mut counter = 0u64;
+    loop {
+        let x = Fq2::new(
+            Fq::from_le_bytes_mod_order(&digest(counter, 0)),
+            Fq::from_le_bytes_mod_order(&digest(counter, 1)),
+        );
+        let greatest = digest(counter, 2)[0] & 1 == 1;
+        if let Some(point) = G2Affine::get_point_from_x(x, greatest) {
+            let point = point.mul_by_cofactor();
+            if !point.is_zero() {
+                return Ok(point);
+            }
+        }
+        counter += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::{BoundCheckCircuit, BoundMode};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use legogroth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+
+    fn circuit(value: Option<u64>) -> BoundCheckCircuit<Fr> {
+        BoundCheckCircuit {
+            min: Some(Fr::from(100u64)),
+            max: Some(Fr::from(110u64)),
+            value: value.map(Fr::from),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        }
+    }
+
+    /// Powers of random `tau`, `alpha` and `beta`, standing in for the output of a phase 1 ceremony
+    fn powers_of_tau(rng: &mut StdRng, size: usize) -> PowersOfTau {
+        let (tau, alpha, beta) = (Fr::rand(rng), Fr::rand(rng), Fr::rand(rng));
+        let mut powers = vec![Fr::one()];
+        for i in 1..2 * size - 1 {
+            powers.push(powers[i - 1] * tau);
+        }
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        let in_g1 = |by: Fr, n: usize| {
+            powers[..n]
+                .iter()
+                .map(|p| g1.mul((by * p).into_repr()).into_affine())
+                .collect()
+        };
+        PowersOfTau {
+            tau_g1: in_g1(Fr::one(), 2 * size - 1),
+            tau_g2: powers[..size]
+                .iter()
+                .map(|p| g2.mul(p.into_repr()).into_affine())
+                .collect(),
+            alpha_tau_g1: in_g1(alpha, size),
+            beta_tau_g1: in_g1(beta, size),
+            beta_g2: g2.mul(beta.into_repr()).into_affine(),
+        }
+    }
+
+    #[test]
+    fn ceremony_from_powers_of_tau() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let powers = powers_of_tau(&mut rng, 128);
+        powers.check(&mut rng).unwrap();
+
+        // Parameters derived from the powers work as they are, and after contributions
+        let initial = powers.params(circuit(None), 1).unwrap();
+        let mut ceremony =
+            Ceremony::from_powers_of_tau(&powers, circuit(None), 1, &mut rng).unwrap();
+        assert_eq!(
+            ceremony.transcript_hash().unwrap(),
+            Ceremony::new(initial.clone())
+                .unwrap()
+                .transcript_hash()
+                .unwrap()
+        );
+        for _ in 0..2 {
+            let before = ceremony.clone();
+            ceremony.contribute(&mut rng).unwrap();
+            Ceremony::verify_contribution(&before, &ceremony, &mut rng).unwrap();
+        }
+        ceremony.verify(&initial, &mut rng).unwrap();
+        for params in [initial, ceremony.into_params()] {
+            let pvk = prepare_verifying_key(&params.vk);
+            let proof =
+                create_random_proof(circuit(Some(105)), Fr::rand(&mut rng), &params, &mut rng)
+                    .unwrap();
+            verify_proof(&pvk, &proof, &[Fr::from(100u64), Fr::from(110u64)]).unwrap();
+            assert!(verify_proof(&pvk, &proof, &[Fr::from(100u64), Fr::from(111u64)]).is_err());
+        }
+
+        // Too few powers for the circuit
+        let few = powers_of_tau(&mut rng, 4);
+        few.check(&mut rng).unwrap();
+        assert!(matches!(
+            few.params(circuit(None), 1),
+            Err(PredicateError::InvalidPowersOfTau("tau_g2"))
+        ));
+
+        // Malformed powers
+        let g1 = G1Affine::prime_subgroup_generator();
+        let other = powers_of_tau(&mut rng, 128);
+        let mut tampered = powers.clone();
+        tampered.tau_g1[5] = other.tau_g1[5];
+        let mut tampered_g2 = powers.clone();
+        tampered_g2.tau_g2[5] = other.tau_g2[5];
+        let mut tampered_alpha = powers.clone();
+        tampered_alpha.alpha_tau_g1[5] = other.alpha_tau_g1[5];
+        let mut tampered_beta = powers.clone();
+        tampered_beta.beta_g2 = other.beta_g2;
+        let mut truncated = powers.clone();
+        truncated.beta_tau_g1.pop();
+        // Powers of a generator other than the standard one
+        let mut shifted = powers.clone();
+        shifted.tau_g1[0] = g1.mul(Fr::from(2u64).into_repr()).into_affine();
+        for (powers, field) in [
+            (tampered, "tau_g1"),
+            (tampered_g2, "tau_g2"),
+            (tampered_alpha, "alpha_tau_g1"),
+            (tampered_beta, "beta_g2"),
+            (truncated, "beta_tau_g1"),
+            (shifted, "tau_g1"),
+        ] {
+            match Ceremony::from_powers_of_tau(&powers, circuit(None), 1, &mut rng) {
+                Err(PredicateError::InvalidPowersOfTau(f)) => assert_eq!(f, field),
+                _ => panic!("expected invalid powers of tau"),
+            }
+        }
+    }
+
+    #[test]
+    fn ceremony_with_many_participants() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        // Stands in for parameters created from the output of phase 1
+        let initial =
+            generate_random_parameters::<Bls12_381, _, _>(circuit(None), 1, &mut rng).unwrap();
+
+        let participants = 4;
+        let mut ceremony = Ceremony::new(initial.clone()).unwrap();
+        let mut hashes = vec![];
+        for _ in 0..participants {
+            // Each participant receives the ceremony from the previous one, serialized
+            let mut bytes = vec![];
+            ceremony.serialize(&mut bytes).unwrap();
+            let before = Ceremony::deserialize(&bytes[..]).unwrap();
+
+            let mut after = before.clone();
+            let hash = after.contribute(&mut rng).unwrap();
+            assert_eq!(
+                Ceremony::verify_contribution(&before, &after, &mut rng).unwrap(),
+                hash
+            );
+            hashes.push(hash);
+            ceremony = after;
+        }
+        assert_eq!(ceremony.contributions().len(), participants);
+        assert_eq!(ceremony.verify(&initial, &mut rng).unwrap(), hashes);
+        assert_ne!(ceremony.params().delta_g1, initial.delta_g1);
+        assert_ne!(
+            ceremony.params().vk.eta_gamma_inv_g1,
+            initial.vk.eta_gamma_inv_g1
+        );
+
+        // Parameters work with the prover and verifier
+        let params = ceremony.into_params();
+        let pvk = prepare_verifying_key(&params.vk);
+        let proof =
+            create_random_proof(circuit(Some(105)), Fr::rand(&mut rng), &params, &mut rng).unwrap();
+        verify_proof(&pvk, &proof, &[Fr::from(100u64), Fr::from(110u64)]).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[Fr::from(100u64), Fr::from(111u64)]).is_err());
+    }
+
+    #[test]
+    fn hash_to_g2_is_stable() {
+        let g1 = G1Affine::prime_subgroup_generator();
+        let r = hash_to_g2(b"transcript".to_vec(), &g1, &g1, &g1).unwrap();
+        assert!(r.is_on_curve() && r.is_in_correct_subgroup_assuming_on_curve());
+        assert_ne!(
+            r,
+            hash_to_g2(b"transcripts".to_vec(), &g1, &g1, &g1).unwrap()
+        );
+        // Transcripts made with an earlier version of the crate verify with later ones
+        let mut bytes = vec![];
+        r.serialize(&mut bytes).unwrap();
+        assert_eq!(
+            bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>(),
+            "cfafce82c01f3dcd960ffc1fe24b095615c949a3d604ec060e76745f613dd928919a2769e8cc3ba1a6d54dc7d34074105a5fc6790afd1eaf2f0b16c487981a4a549d89776ece120ba16e20d2ea002314f3955da9d5d2c7ecb814ddcdfc24f489"
+        );
+    }
+
+    #[test]
+    fn invalid_contributions() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let initial =
+            generate_random_parameters::<Bls12_381, _, _>(circuit(None), 1, &mut rng).unwrap();
+        let mut before = Ceremony::new(initial.clone()).unwrap();
+        before.contribute(&mut rng).unwrap();
+        let mut after = before.clone();
+        after.contribute(&mut rng).unwrap();
+        Ceremony::verify_contribution(&before, &after, &mut rng).unwrap();
+        after.verify(&initial, &mut rng).unwrap();
+
+        let check = |tampered: &Ceremony, rng: &mut StdRng| {
+            assert!(Ceremony::verify_contribution(&before, tampered, rng).is_err());
+            assert!(tampered.verify(&initial, rng).is_err());
+        };
+
+        // Participant replaces `delta` with a value of their choice rather than multiplying it, which would let
+        // them forge proofs
+        let mut tampered = after.clone();
+        let delta = Fr::rand(&mut rng);
+        tampered.params.delta_g1 = G1Affine::prime_subgroup_generator()
+            .mul(delta.into_repr())
+            .into_affine();
+        tampered.params.vk.delta_g2 = G2Affine::prime_subgroup_generator()
+            .mul(delta.into_repr())
+            .into_affine();
+        tampered.contributions[1].delta_after = tampered.params.delta_g1;
+        assert!(matches!(
+            Ceremony::verify_contribution(&before, &tampered, &mut rng),
+            Err(PredicateError::InvalidContribution(1))
+        ));
+        check(&tampered, &mut rng);
+
+        // Proof of knowledge of another contribution
+        let mut tampered = after.clone();
+        tampered.contributions[1].r_delta = tampered.contributions[0].r_delta;
+        check(&tampered, &mut rng);
+        let mut tampered = after.clone();
+        tampered.contributions[1].s_eta = tampered.contributions[1].s_delta;
+        check(&tampered, &mut rng);
+
+        // Parameters not updated consistently with the contribution
+        let mut tampered = after.clone();
+        tampered.params = before.params.clone();
+        assert!(matches!(
+            Ceremony::verify_contribution(&before, &tampered, &mut rng),
+            Err(PredicateError::CeremonyParametersMismatch)
+        ));
+        check(&tampered, &mut rng);
+        let mut tampered = after.clone();
+        tampered.params.h_query[0] = tampered.params.h_query[1];
+        check(&tampered, &mut rng);
+        let mut tampered = after.clone();
+        tampered.params.l_query.pop();
+        check(&tampered, &mut rng);
+        let mut tampered = after.clone();
+        tampered.params.eta_delta_inv_g1 = before.params.eta_delta_inv_g1;
+        check(&tampered, &mut rng);
+        let mut tampered = after.clone();
+        tampered.params.vk.delta_g2 = before.params.vk.delta_g2;
+        check(&tampered, &mut rng);
+        // or changed where a contribution does not change the parameters
+        let mut tampered = after.clone();
+        tampered.params.vk.alpha_g1 = tampered.params.beta_g1;
+        check(&tampered, &mut rng);
+
+        // Contribution dropped or made to other parameters
+        let mut tampered = after.clone();
+        tampered.contributions.remove(0);
+        assert!(tampered.verify(&initial, &mut rng).is_err());
+        let other =
+            generate_random_parameters::<Bls12_381, _, _>(circuit(None), 1, &mut rng).unwrap();
+        assert!(matches!(
+            after.verify(&other, &mut rng),
+            Err(PredicateError::CeremonyParametersMismatch)
+        ));
+    }
+}
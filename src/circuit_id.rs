@@ -0,0 +1,408 @@
+use crate::error::PredicateError;
+use crate::presentation::Predicate;
+use crate::Fr;
+use ark_bls12_381::Bls12_381;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::io::{Read, Write};
+use ark_std::rand::RngCore;
+use ark_std::string::String;
+use blake2::{Blake2b, Digest};
+use legogroth16::{generate_random_parameters, ProvingKey, VerifyingKey};
+
+// NOTE: The ID in an `IdentifiedKey` is only a claim of whoever generated the key. Checking it with
+// `check_circuit` catches keys generated for another circuit or configuration, eg. for values of a different
+// bit-size, and keys whose shape does not match the circuit. It cannot catch a key crafted for a different
+// circuit of the same shape and labelled with this circuit's ID, which needs a trusted setup.
+
+/// Domain separator for the digest of a circuit
+const CIRCUIT_ID_LABEL: &[u8] = b"BBS-predicate-circuit";
+
+/// Deterministic ID of a circuit computed from its R1CS matrices. Circuits differing in configuration like the
+/// bit-size of values or the number of values have different IDs but circuits differing only in the values of
+/// the witnesses and public inputs have the same ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CircuitId {
+    pub public_inputs_count: usize,
+    pub commit_witness_count: usize,
+    /// Blake2b hash of the counts and the constraint matrices
+    pub digest: [u8; 64],
+}
+
+/// A LegoGroth16 key along with the ID of the circuit it was generated for
+#[derive(Clone, Debug)]
+pub struct IdentifiedKey<K> {
+    pub circuit_id: CircuitId,
+    pub key: K,
+}
+
+pub type IdentifiedProvingKey = IdentifiedKey<ProvingKey<Bls12_381>>;
+pub type IdentifiedVerifyingKey = IdentifiedKey<VerifyingKey<Bls12_381>>;
+
+impl CircuitId {
+    /// Compute the ID by synthesizing the circuit in setup mode, so the circuit's values can be `None`
+    pub fn new<C: ConstraintSynthesizer<Fr>>(
+        circuit: C,
+        commit_witness_count: usize,
+    ) -> Result<Self, PredicateError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+        let matrices = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+        // The 1st instance variable is the constant 1
+        let public_inputs_count = matrices.num_instance_variables - 1;
+
+        let mut bytes = CIRCUIT_ID_LABEL.to_vec();
+        for n in [
+            public_inputs_count,
+            commit_witness_count,
+            matrices.num_witness_variables,
+            matrices.num_constraints,
+        ] {
+            (n as u64).serialize(&mut bytes)?;
+        }
+        for matrix in [&matrices.a, &matrices.b, &matrices.c] {
+            for row in matrix {
+                (row.len() as u64).serialize(&mut bytes)?;
+                for (coeff, index) in row {
+                    coeff.serialize(&mut bytes)?;
+                    (*index as u64).serialize(&mut bytes)?;
+                }
+            }
+        }
+        let mut hasher = Blake2b::new();
+        hasher.update(&bytes);
+        let mut digest = [0u8; 64];
+        digest.copy_from_slice(&hasher.finalize());
+        Ok(Self {
+            public_inputs_count,
+            commit_witness_count,
+            digest,
+        })
+    }
+
+    /// Hex encoded digest
+    pub fn to_hex(&self) -> String {
+        self.digest
+            .iter()
+            .map(|b| ark_std::format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Check that `vk` has 1 element in `gamma_abc_g1` for the constant 1, each public input and each committed
+    /// witness
+    pub fn check_verifying_key(&self, vk: &VerifyingKey<Bls12_381>) -> Result<(), PredicateError> {
+        let expected = 1 + self.public_inputs_count + self.commit_witness_count;
+        if vk.gamma_abc_g1.len() != expected {
+            return Err(PredicateError::IncompatibleVerifyingKey {
+                expected,
+                found: vk.gamma_abc_g1.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Predicate {
+    /// ID of this predicate's circuit
+    pub fn circuit_id(&self) -> Result<CircuitId, PredicateError> {
+        CircuitId::new(self.circuit(None)?, self.committed_messages().len())
+    }
+}
+
+impl IdentifiedProvingKey {
+    /// Generate the key for `circuit` and label it with the circuit's ID
+    pub fn generate<C: ConstraintSynthesizer<Fr> + Clone, R: RngCore>(
+        circuit: C,
+        commit_witness_count: usize,
+        rng: &mut R,
+    ) -> Result<Self, PredicateError> {
+        let circuit_id = CircuitId::new(circuit.clone(), commit_witness_count)?;
+        let key =
+            generate_random_parameters::<Bls12_381, _, _>(circuit, commit_witness_count, rng)?;
+        Ok(Self { circuit_id, key })
+    }
+
+    pub fn verifying_key(&self) -> IdentifiedVerifyingKey {
+        IdentifiedKey {
+            circuit_id: self.circuit_id,
+            key: self.key.vk.clone(),
+        }
+    }
+
+    /// Same as `IdentifiedVerifyingKey::check_circuit`
+    pub fn check_circuit<C: ConstraintSynthesizer<Fr>>(
+        &self,
+        circuit: C,
+        commit_witness_count: usize,
+    ) -> Result<(), PredicateError> {
+        check_circuit(
+            &self.circuit_id,
+            &self.key.vk,
+            circuit,
+            commit_witness_count,
+        )
+    }
+}
+
+impl IdentifiedVerifyingKey {
+    /// Check that the key is labelled with the ID of `circuit`, computed by synthesizing it, and that the key has
+    /// the shape of the circuit, i.e. the number of public inputs and committed witnesses
+    pub fn check_circuit<C: ConstraintSynthesizer<Fr>>(
+        &self,
+        circuit: C,
+        commit_witness_count: usize,
+    ) -> Result<(), PredicateError> {
+        check_circuit(&self.circuit_id, &self.key, circuit, commit_witness_count)
+    }
+}
+
+fn check_circuit<C: ConstraintSynthesizer<Fr>>(
+    circuit_id: &CircuitId,
+    vk: &VerifyingKey<Bls12_381>,
+    circuit: C,
+    commit_witness_count: usize,
+) -> Result<(), PredicateError> {
+    if *circuit_id != CircuitId::new(circuit, commit_witness_count)? {
+        return Err(PredicateError::CircuitIdMismatch);
+    }
+    circuit_id.check_verifying_key(vk)
+}
+
+impl CanonicalSerialize for CircuitId {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        (self.public_inputs_count as u64).serialize(&mut writer)?;
+        (self.commit_witness_count as u64).serialize(&mut writer)?;
+        writer.write_all(&self.digest)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self) -> usize {
+        8 + 8 + self.digest.len()
+    }
+}
+
+impl CanonicalDeserialize for CircuitId {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let public_inputs_count = u64::deserialize(&mut reader)? as usize;
+        let commit_witness_count = u64::deserialize(&mut reader)? as usize;
+        let mut digest = [0u8; 64];
+        reader.read_exact(&mut digest)?;
+        Ok(Self {
+            public_inputs_count,
+            commit_witness_count,
+            digest,
+        })
+    }
+}
+
+impl<K: CanonicalSerialize> CanonicalSerialize for IdentifiedKey<K> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.circuit_id.serialize(&mut writer)?;
+        self.key.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.circuit_id.serialized_size() + self.key.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.circuit_id.serialize(&mut writer)?;
+        self.key.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.circuit_id.serialized_size() + self.key.uncompressed_size()
+    }
+}
+
+impl<K: CanonicalDeserialize> CanonicalDeserialize for IdentifiedKey<K> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Self {
+            circuit_id: CircuitId::deserialize(&mut reader)?,
+            key: K::deserialize(&mut reader)?,
+        })
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        Ok(Self {
+            circuit_id: CircuitId::deserialize(&mut reader)?,
+            key: K::deserialize_uncompressed(&mut reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::{Bound, BoundCheckCircuit, BoundMode};
+    use crate::sum::{SumBoundCheckCircuit, SumCompareCircuit};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    fn bound_circuit(bits: Option<usize>, mode: BoundMode) -> BoundCheckCircuit<Fr> {
+        BoundCheckCircuit {
+            min: None,
+            max: None,
+            value: None,
+            bits,
+            mode,
+        }
+    }
+
+    fn sum_bound_circuit(count: usize, bits: usize) -> SumBoundCheckCircuit<Fr> {
+        SumBoundCheckCircuit {
+            min: None,
+            max: None,
+            count,
+            values: None,
+            bits,
+        }
+    }
+
+    #[test]
+    fn circuit_ids() {
+        let id = CircuitId::new(bound_circuit(Some(16), BoundMode::default()), 1).unwrap();
+        assert_eq!(id.public_inputs_count, 2);
+        assert_eq!(id.commit_witness_count, 1);
+        // Deterministic and independent of the values
+        assert_eq!(
+            id,
+            CircuitId::new(bound_circuit(Some(16), BoundMode::default()), 1).unwrap()
+        );
+        let circuit = BoundCheckCircuit {
+            min: Some(Fr::from(100u64)),
+            max: Some(Fr::from(110u64)),
+            value: Some(Fr::from(105u64)),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        assert_eq!(id, CircuitId::new(circuit, 1).unwrap());
+
+        // Different configurations
+        let ids = vec![
+            id,
+            CircuitId::new(bound_circuit(Some(32), BoundMode::default()), 1).unwrap(),
+            CircuitId::new(bound_circuit(None, BoundMode::default()), 1).unwrap(),
+            CircuitId::new(
+                bound_circuit(Some(16), BoundMode::LowerOnly(Bound::Strict)),
+                1,
+            )
+            .unwrap(),
+            CircuitId::new(
+                bound_circuit(
+                    Some(16),
+                    BoundMode::Both {
+                        lower: Bound::Inclusive,
+                        upper: Bound::Strict,
+                    },
+                ),
+                1,
+            )
+            .unwrap(),
+            CircuitId::new(sum_bound_circuit(2, 16), 2).unwrap(),
+            CircuitId::new(sum_bound_circuit(3, 16), 3).unwrap(),
+            CircuitId::new(sum_bound_circuit(2, 32), 2).unwrap(),
+            CircuitId::new(
+                SumCompareCircuit::<Fr> {
+                    smalls_count: 2,
+                    larges_count: 1,
+                    smalls: None,
+                    larges: None,
+                    bits: 16,
+                },
+                3,
+            )
+            .unwrap(),
+            CircuitId::new(
+                SumCompareCircuit::<Fr> {
+                    smalls_count: 1,
+                    larges_count: 2,
+                    smalls: None,
+                    larges: None,
+                    bits: 16,
+                },
+                3,
+            )
+            .unwrap(),
+        ];
+        for i in 0..ids.len() {
+            for j in i + 1..ids.len() {
+                assert_ne!(ids[i], ids[j]);
+                assert_ne!(ids[i].to_hex(), ids[j].to_hex());
+            }
+        }
+        // The committed witness count is part of the ID
+        assert_ne!(CircuitId::new(sum_bound_circuit(2, 16), 1).unwrap(), ids[5]);
+
+        let mut bytes = vec![];
+        id.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), id.serialized_size());
+        assert_eq!(CircuitId::deserialize(&bytes[..]).unwrap(), id);
+    }
+
+    #[test]
+    fn identified_keys() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let circuit = sum_bound_circuit(2, 16);
+        let pk = IdentifiedProvingKey::generate(circuit.clone(), 2, &mut rng).unwrap();
+        let vk = pk.verifying_key();
+        pk.check_circuit(circuit.clone(), 2).unwrap();
+        vk.check_circuit(circuit.clone(), 2).unwrap();
+
+        // The ID is serialized with the key
+        let mut bytes = vec![];
+        vk.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), vk.serialized_size());
+        let deserialized = IdentifiedVerifyingKey::deserialize(&bytes[..]).unwrap();
+        assert_eq!(deserialized.circuit_id, pk.circuit_id);
+        deserialized.check_circuit(circuit.clone(), 2).unwrap();
+        let mut bytes = vec![];
+        pk.serialize_uncompressed(&mut bytes).unwrap();
+        let deserialized = IdentifiedProvingKey::deserialize_uncompressed(&bytes[..]).unwrap();
+        deserialized.check_circuit(circuit.clone(), 2).unwrap();
+
+        // Key for another circuit
+        assert!(matches!(
+            vk.check_circuit(sum_bound_circuit(2, 32), 2),
+            Err(PredicateError::CircuitIdMismatch)
+        ));
+        assert!(matches!(
+            vk.check_circuit(sum_bound_circuit(3, 16), 3),
+            Err(PredicateError::CircuitIdMismatch)
+        ));
+
+        // Key for another circuit labelled with this circuit's ID
+        let other = IdentifiedProvingKey::generate(
+            bound_circuit(Some(16), BoundMode::LowerOnly(Bound::Strict)),
+            1,
+            &mut rng,
+        )
+        .unwrap();
+        let relabelled = IdentifiedKey {
+            circuit_id: vk.circuit_id,
+            key: other.key.vk.clone(),
+        };
+        assert!(matches!(
+            relabelled.check_circuit(circuit.clone(), 2),
+            Err(PredicateError::IncompatibleVerifyingKey {
+                expected: 5,
+                found: 3
+            })
+        ));
+
+        // Key with an element of `gamma_abc_g1` removed
+        let mut tampered = vk.clone();
+        tampered.key.gamma_abc_g1.pop();
+        assert!(matches!(
+            tampered.check_circuit(circuit, 2),
+            Err(PredicateError::IncompatibleVerifyingKey {
+                expected: 5,
+                found: 4
+            })
+        ));
+    }
+}
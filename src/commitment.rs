@@ -0,0 +1,164 @@
+use crate::error::PredicateError;
+use ark_ec::PairingEngine;
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+use legogroth16::VerifyingKey;
+
+// NOTE: The LegoGroth16 proof contains a commitment `d` to the committed witnesses. The verifying key's
+// `gamma_abc_g1` has 1 element for the constant 1, followed by 1 for each public input and 1 for each committed
+// witness, and `d = sum(gamma_abc_g1[1 + public_inputs_count + i] * w_i) + eta_gamma_inv_g1 * v` where `w_i` is
+// the `i`th committed witness and `v` is the randomness passed to `create_random_proof`.
+
+/// Bases of the commitment `d` in a LegoGroth16 proof, to be used in `PedersenCommitmentStmt`. The opening of the
+/// commitment is created with `commitment_opening`. Fails if the verifying key is not for a circuit with the given
+/// number of public inputs and committed witnesses.
+pub fn commitment_bases<E: PairingEngine>(
+    vk: &VerifyingKey<E>,
+    public_inputs_count: usize,
+    commit_witness_count: usize,
+) -> Result<Vec<E::G1Affine>, PredicateError> {
+    let expected = 1 + public_inputs_count + commit_witness_count;
+    if vk.gamma_abc_g1.len() != expected {
+        return Err(PredicateError::IncompatibleVerifyingKey {
+            expected,
+            found: vk.gamma_abc_g1.len(),
+        });
+    }
+    let mut bases = vk.gamma_abc_g1[1 + public_inputs_count..].to_vec();
+    bases.push(vk.eta_gamma_inv_g1);
+    Ok(bases)
+}
+
+/// Opening of the commitment `d` in a LegoGroth16 proof, to be used in `Witness::PedersenCommitment`. `committed`
+/// are the committed witnesses in the order the circuit allocates them and `v` is the randomness passed to
+/// `create_random_proof`.
+pub fn commitment_opening<F: PrimeField>(committed: &[F], v: F) -> Vec<F> {
+    let mut opening = Vec::with_capacity(committed.len() + 1);
+    opening.extend_from_slice(committed);
+    opening.push(v);
+    opening
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::not_equal::{NotEqualCircuit, NotEqualPublicCircuit};
+    use crate::sum::LinearCombinationBoundCircuit;
+    use crate::tests::*;
+    use ark_bls12_381::{Bls12_381, G1Projective};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_relations::r1cs::ConstraintSynthesizer;
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        UniformRand,
+    };
+    use legogroth16::{create_random_proof, generate_random_parameters, verify_witness_commitment};
+
+    /// Create a proof for `circuit` and check that the bases and opening match the commitment in it
+    fn check_bases_and_opening<C: ConstraintSynthesizer<Fr>>(
+        setup_circuit: C,
+        circuit: C,
+        public_inputs_count: usize,
+        committed: &[Fr],
+    ) {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let params =
+            generate_random_parameters::<Bls12_381, _, _>(setup_circuit, committed.len(), &mut rng)
+                .unwrap();
+        let v = Fr::rand(&mut rng);
+        let snark_proof = create_random_proof(circuit, v, &params, &mut rng).unwrap();
+        verify_witness_commitment(&params.vk, &snark_proof, public_inputs_count, committed, &v)
+            .unwrap();
+
+        let bases = commitment_bases(&params.vk, public_inputs_count, committed.len()).unwrap();
+        let opening = commitment_opening(committed, v);
+        assert_eq!(bases.len(), committed.len() + 1);
+        assert_eq!(opening.len(), committed.len() + 1);
+        let commitment = bases
+            .iter()
+            .zip(opening.iter())
+            .map(|(b, s)| b.mul(s.into_repr()))
+            .sum::<G1Projective>();
+        assert_eq!(commitment.into_affine(), snark_proof.d);
+
+        // Wrong counts are rejected
+        assert!(commitment_bases(&params.vk, public_inputs_count + 1, committed.len()).is_err());
+        assert!(commitment_bases(&params.vk, public_inputs_count, committed.len() + 1).is_err());
+        // Counts with the right total give bases that don't match the commitment. The extra base is that of the
+        // last public input, so it is opened with a nonzero value to not drop out of the sum.
+        if public_inputs_count > 0 {
+            let bases =
+                commitment_bases(&params.vk, public_inputs_count - 1, committed.len() + 1).unwrap();
+            let opening = commitment_opening(&[&[Fr::from(1u64)], committed].concat(), v);
+            let commitment = bases
+                .iter()
+                .zip(opening.iter())
+                .map(|(b, s)| b.mul(s.into_repr()))
+                .sum::<G1Projective>();
+            assert_ne!(commitment.into_affine(), snark_proof.d);
+        }
+    }
+
+    #[test]
+    fn bases_for_no_public_input() {
+        let committed = [Fr::from(10u64), Fr::from(20u64)];
+        check_bases_and_opening(
+            NotEqualCircuit {
+                value_1: None,
+                value_2: None,
+            },
+            NotEqualCircuit {
+                value_1: Some(committed[0]),
+                value_2: Some(committed[1]),
+            },
+            0,
+            &committed,
+        );
+    }
+
+    #[test]
+    fn bases_for_one_public_input() {
+        let committed = [Fr::from(10u64)];
+        check_bases_and_opening(
+            NotEqualPublicCircuit {
+                value: None,
+                public: None,
+            },
+            NotEqualPublicCircuit {
+                value: Some(committed[0]),
+                public: Some(Fr::from(20u64)),
+            },
+            1,
+            &committed,
+        );
+    }
+
+    #[test]
+    fn bases_for_many_public_inputs() {
+        // min, max and 3 coefficients are public
+        let committed = [Fr::from(10u64), Fr::from(20u64), Fr::from(30u64)];
+        let coefficients = vec![Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+        check_bases_and_opening(
+            LinearCombinationBoundCircuit {
+                min: None,
+                max: None,
+                count: 3,
+                coefficients: None,
+                values: None,
+                bits: 8,
+                coefficient_bits: 4,
+            },
+            LinearCombinationBoundCircuit {
+                min: Some(Fr::from(100u64)),
+                max: Some(Fr::from(200u64)),
+                count: 3,
+                coefficients: Some(coefficients),
+                values: Some(committed.to_vec()),
+                bits: 8,
+                coefficient_bits: 4,
+            },
+            5,
+            &committed,
+        );
+    }
+}
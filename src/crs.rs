@@ -0,0 +1,338 @@
+use crate::ceremony::same_ratio;
+use crate::error::PredicateError;
+use crate::Fr;
+use ark_bls12_381::Bls12_381;
+use ark_ec::msm::VariableBaseMSM;
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{PrimeField, Zero};
+use ark_std::fmt;
+use ark_std::rand::RngCore;
+use ark_std::{vec::Vec, UniformRand};
+use legogroth16::{ProvingKey, VerifyingKey};
+
+// NOTE: Only relations between elements whose discrete logarithms are related in a way that can be checked with
+// pairings are checked, i.e. that `beta`, `delta` and each element of the B query are the same in G1 and G2 and
+// that `eta_delta_inv_g1 * delta = eta_gamma_inv_g1 * gamma`. The keys need not use the standard generators, so
+// `beta` and the B query are compared to `delta` rather than to the generators, and `delta` is blamed when both
+// comparisons fail. Elements like `alpha_g1`, `a_query`, `h_query` and
+// `l_query` have no counterpart to be checked against so only their lengths and that they are not the identity
+// are checked. Passing these checks does not mean that nobody knows the trapdoors, which needs a ceremony.
+
+/// Reason a proving key and verifying key pair is malformed. Elements are named as the fields of the keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CrsError {
+    /// The element of the verifying key differs from the one in the proving key
+    VerifyingKeyMismatch(&'static str),
+    /// The number of elements is inconsistent with the circuit or with the other elements
+    Length(&'static str),
+    /// The element, or an element of the vector, is the identity
+    Identity(&'static str),
+    /// The element, or an element of the vector, fails the pairing check relating it to the other elements
+    Pairing(&'static str),
+}
+
+impl fmt::Display for CrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VerifyingKeyMismatch(field) => {
+                write!(
+                    f,
+                    "{} of the verifying key differs from the proving key's",
+                    field
+                )
+            }
+            Self::Length(field) => write!(f, "{} has the wrong number of elements", field),
+            Self::Identity(field) => write!(f, "{} is or contains the identity", field),
+            Self::Pairing(field) => write!(f, "{} fails its pairing check", field),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CrsError {}
+
+/// Check that `pk` and `vk` are a well-formed pair of keys for a circuit with the given number of public inputs and
+/// committed witnesses
+pub fn check_keys<R: RngCore>(
+    pk: &ProvingKey<Bls12_381>,
+    vk: &VerifyingKey<Bls12_381>,
+    public_inputs_count: usize,
+    commit_witness_count: usize,
+    rng: &mut R,
+) -> Result<(), PredicateError> {
+    check_pair(pk, vk)?;
+    check_lengths(pk, public_inputs_count, commit_witness_count)?;
+    check_identities(pk, public_inputs_count)?;
+    check_pairings(pk, rng)?;
+    Ok(())
+}
+
+fn check_pair(pk: &ProvingKey<Bls12_381>, vk: &VerifyingKey<Bls12_381>) -> Result<(), CrsError> {
+    let fields = [
+        ("alpha_g1", pk.vk.alpha_g1 == vk.alpha_g1),
+        ("beta_g2", pk.vk.beta_g2 == vk.beta_g2),
+        ("gamma_g2", pk.vk.gamma_g2 == vk.gamma_g2),
+        ("delta_g2", pk.vk.delta_g2 == vk.delta_g2),
+        ("gamma_abc_g1", pk.vk.gamma_abc_g1 == vk.gamma_abc_g1),
+        (
+            "eta_gamma_inv_g1",
+            pk.vk.eta_gamma_inv_g1 == vk.eta_gamma_inv_g1,
+        ),
+    ];
+    match fields.iter().find(|(_, same)| !same) {
+        Some((name, _)) => Err(CrsError::VerifyingKeyMismatch(name)),
+        None => Ok(()),
+    }
+}
+
+fn check_lengths(
+    pk: &ProvingKey<Bls12_381>,
+    public_inputs_count: usize,
+    commit_witness_count: usize,
+) -> Result<(), CrsError> {
+    // `gamma_abc_g1` has 1 element for the constant 1, each public input and each committed witness, `l_query`
+    // has 1 for each other witness and the A and B queries have 1 for each of all these
+    if pk.vk.gamma_abc_g1.len() != 1 + public_inputs_count + commit_witness_count {
+        return Err(CrsError::Length("gamma_abc_g1"));
+    }
+    if pk.vk.gamma_abc_g1.len() + pk.l_query.len() != pk.a_query.len() {
+        return Err(CrsError::Length("l_query"));
+    }
+    if pk.b_g1_query.len() != pk.a_query.len() {
+        return Err(CrsError::Length("b_g1_query"));
+    }
+    if pk.b_g2_query.len() != pk.a_query.len() {
+        return Err(CrsError::Length("b_g2_query"));
+    }
+    if pk.h_query.is_empty() {
+        return Err(CrsError::Length("h_query"));
+    }
+    Ok(())
+}
+
+fn check_identities(
+    pk: &ProvingKey<Bls12_381>,
+    public_inputs_count: usize,
+) -> Result<(), CrsError> {
+    let g1 = [
+        ("alpha_g1", pk.vk.alpha_g1),
+        ("beta_g1", pk.beta_g1),
+        ("delta_g1", pk.delta_g1),
+        ("eta_gamma_inv_g1", pk.vk.eta_gamma_inv_g1),
+        ("eta_delta_inv_g1", pk.eta_delta_inv_g1),
+    ];
+    let g2 = [
+        ("beta_g2", pk.vk.beta_g2),
+        ("gamma_g2", pk.vk.gamma_g2),
+        ("delta_g2", pk.vk.delta_g2),
+    ];
+    if let Some((name, _)) = g1.iter().find(|(_, e)| e.is_zero()) {
+        return Err(CrsError::Identity(name));
+    }
+    if let Some((name, _)) = g2.iter().find(|(_, e)| e.is_zero()) {
+        return Err(CrsError::Identity(name));
+    }
+    // The bases of the commitment to the committed witnesses. Elements of the other vectors are the identity for
+    // variables not used in the corresponding part of the constraints.
+    if pk.vk.gamma_abc_g1[1 + public_inputs_count..]
+        .iter()
+        .any(|e| e.is_zero())
+    {
+        return Err(CrsError::Identity("gamma_abc_g1"));
+    }
+    if pk.h_query.iter().any(|e| e.is_zero()) {
+        return Err(CrsError::Identity("h_query"));
+    }
+    Ok(())
+}
+
+fn check_pairings<R: RngCore>(pk: &ProvingKey<Bls12_381>, rng: &mut R) -> Result<(), CrsError> {
+    // `beta / delta` is the same in G1 and G2
+    let beta_ok = same_ratio((pk.delta_g1, pk.beta_g1), (pk.vk.delta_g2, pk.vk.beta_g2));
+    // and so is `b / delta` for the same random linear combination `b` of both B queries
+    let coeffs = (0..pk.b_g1_query.len())
+        .map(|_| Fr::rand(rng).into_repr())
+        .collect::<Vec<_>>();
+    let b_g1 = VariableBaseMSM::multi_scalar_mul(&pk.b_g1_query, &coeffs).into_affine();
+    let b_g2 = VariableBaseMSM::multi_scalar_mul(&pk.b_g2_query, &coeffs).into_affine();
+    let b_ok = same_ratio((pk.delta_g1, b_g1), (pk.vk.delta_g2, b_g2));
+    match (beta_ok, b_ok) {
+        (false, false) => return Err(CrsError::Pairing("delta_g1")),
+        (false, true) => return Err(CrsError::Pairing("beta_g1")),
+        (true, false) => return Err(CrsError::Pairing("b_g1_query")),
+        (true, true) => {}
+    }
+    if Bls12_381::pairing(pk.eta_delta_inv_g1, pk.vk.delta_g2)
+        != Bls12_381::pairing(pk.vk.eta_gamma_inv_g1, pk.vk.gamma_g2)
+    {
+        return Err(CrsError::Pairing("eta_delta_inv_g1"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sum::SumBoundCheckCircuit;
+    use ark_bls12_381::{G1Affine, G2Affine};
+    use ark_ec::AffineCurve;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use legogroth16::generate_random_parameters;
+
+    type Corruption = fn(&mut ProvingKey<Bls12_381>);
+
+    /// A different element of the same group
+    fn shift<G: AffineCurve>(e: G) -> G {
+        (e.into_projective() + G::prime_subgroup_generator().into_projective()).into_affine()
+    }
+
+    #[test]
+    fn corrupted_keys() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let circuit = SumBoundCheckCircuit::<Fr> {
+            min: None,
+            max: None,
+            count: 2,
+            values: None,
+            bits: 16,
+        };
+        // min and max are public
+        let pk = generate_random_parameters::<Bls12_381, _, _>(circuit, 2, &mut rng).unwrap();
+        check_keys(&pk, &pk.vk, 2, 2, &mut rng).unwrap();
+
+        assert!(matches!(
+            check_keys(&pk, &pk.vk, 1, 2, &mut rng),
+            Err(PredicateError::InvalidCrs(CrsError::Length("gamma_abc_g1")))
+        ));
+        assert!(matches!(
+            check_keys(&pk, &pk.vk, 2, 3, &mut rng),
+            Err(PredicateError::InvalidCrs(CrsError::Length("gamma_abc_g1")))
+        ));
+
+        // Verifying key not from the proving key
+        let mut vk = pk.vk.clone();
+        vk.delta_g2 = shift(vk.delta_g2);
+        assert!(matches!(
+            check_keys(&pk, &vk, 2, 2, &mut rng),
+            Err(PredicateError::InvalidCrs(CrsError::VerifyingKeyMismatch(
+                "delta_g2"
+            )))
+        ));
+
+        // Each field of the proving key corrupted, along with the verifying key for the verifying key's fields
+        let corruptions: Vec<(Corruption, CrsError)> = vec![
+            (
+                |pk| pk.vk.alpha_g1 = G1Affine::zero(),
+                CrsError::Identity("alpha_g1"),
+            ),
+            (
+                |pk| pk.vk.beta_g2 = shift(pk.vk.beta_g2),
+                CrsError::Pairing("beta_g1"),
+            ),
+            (
+                |pk| pk.vk.beta_g2 = G2Affine::zero(),
+                CrsError::Identity("beta_g2"),
+            ),
+            (
+                |pk| pk.vk.gamma_g2 = shift(pk.vk.gamma_g2),
+                CrsError::Pairing("eta_delta_inv_g1"),
+            ),
+            (
+                |pk| pk.vk.gamma_g2 = G2Affine::zero(),
+                CrsError::Identity("gamma_g2"),
+            ),
+            (
+                |pk| pk.vk.delta_g2 = shift(pk.vk.delta_g2),
+                CrsError::Pairing("delta_g1"),
+            ),
+            (
+                |pk| {
+                    pk.vk.gamma_abc_g1.pop();
+                },
+                CrsError::Length("gamma_abc_g1"),
+            ),
+            (
+                |pk| *pk.vk.gamma_abc_g1.last_mut().unwrap() = G1Affine::zero(),
+                CrsError::Identity("gamma_abc_g1"),
+            ),
+            (
+                |pk| pk.vk.eta_gamma_inv_g1 = shift(pk.vk.eta_gamma_inv_g1),
+                CrsError::Pairing("eta_delta_inv_g1"),
+            ),
+            (
+                |pk| pk.vk.eta_gamma_inv_g1 = G1Affine::zero(),
+                CrsError::Identity("eta_gamma_inv_g1"),
+            ),
+            (
+                |pk| pk.beta_g1 = shift(pk.beta_g1),
+                CrsError::Pairing("beta_g1"),
+            ),
+            (
+                |pk| pk.beta_g1 = G1Affine::zero(),
+                CrsError::Identity("beta_g1"),
+            ),
+            (
+                |pk| pk.delta_g1 = shift(pk.delta_g1),
+                CrsError::Pairing("delta_g1"),
+            ),
+            (
+                |pk| pk.delta_g1 = G1Affine::zero(),
+                CrsError::Identity("delta_g1"),
+            ),
+            (
+                |pk| pk.eta_delta_inv_g1 = shift(pk.eta_delta_inv_g1),
+                CrsError::Pairing("eta_delta_inv_g1"),
+            ),
+            (
+                |pk| pk.eta_delta_inv_g1 = G1Affine::zero(),
+                CrsError::Identity("eta_delta_inv_g1"),
+            ),
+            // The A query has 1 element for each variable, as many as in `gamma_abc_g1` and `l_query`
+            (
+                |pk| {
+                    pk.a_query.pop();
+                },
+                CrsError::Length("l_query"),
+            ),
+            (
+                |pk| {
+                    pk.b_g1_query.pop();
+                },
+                CrsError::Length("b_g1_query"),
+            ),
+            (
+                |pk| pk.b_g1_query[0] = shift(pk.b_g1_query[0]),
+                CrsError::Pairing("b_g1_query"),
+            ),
+            (
+                |pk| {
+                    pk.b_g2_query.pop();
+                },
+                CrsError::Length("b_g2_query"),
+            ),
+            (
+                |pk| pk.b_g2_query[0] = shift(pk.b_g2_query[0]),
+                CrsError::Pairing("b_g1_query"),
+            ),
+            (|pk| pk.h_query.clear(), CrsError::Length("h_query")),
+            (
+                |pk| pk.h_query[0] = G1Affine::zero(),
+                CrsError::Identity("h_query"),
+            ),
+            (
+                |pk| {
+                    pk.l_query.pop();
+                },
+                CrsError::Length("l_query"),
+            ),
+        ];
+        for (corrupt, expected) in corruptions {
+            let mut corrupted = pk.clone();
+            corrupt(&mut corrupted);
+            match check_keys(&corrupted, &corrupted.vk, 2, 2, &mut rng) {
+                Err(PredicateError::InvalidCrs(e)) => assert_eq!(e, expected),
+                r => panic!("expected {:?}, got {:?}", expected, r),
+            }
+        }
+    }
+}
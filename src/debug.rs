@@ -0,0 +1,223 @@
+use crate::error::PredicateError;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{
+    ConstraintLayer, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, TracingMode,
+};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Registry;
+
+// NOTE: Circuits put each logical step, like the range checks, the summation and the comparison, in a namespace
+// using `ns!`. Namespaces cost nothing unless a `ConstraintLayer` is listening, which is only the case when a
+// circuit is synthesized by `synthesize_with_namespaces` so the path of namespaces of each constraint is recorded.
+
+/// Synthesize `circuit` in a new constraint system recording the namespaces each constraint is created in. The
+/// assignments of the returned constraint system can be changed before calling `which_is_unsatisfied`.
+pub fn synthesize_with_namespaces<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<ConstraintSystemRef<F>, PredicateError> {
+    let subscriber = Registry::default().with(ConstraintLayer::new(TracingMode::OnlyConstraints));
+    tracing::subscriber::with_default(subscriber, || {
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone())?;
+        Ok(cs)
+    })
+}
+
+/// Path of namespaces, outermost first and separated by `/`, of the first unsatisfied constraint of a
+/// constraint system created by `synthesize_with_namespaces`, eg. "comparison/sum <= max". `None` if all
+/// constraints are satisfied. Namespaces of the gadgets used by the circuit are left out.
+pub fn which_is_unsatisfied<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+) -> Result<Option<String>, PredicateError> {
+    Ok(cs
+        .which_is_unsatisfied()?
+        .map(|trace| namespace_path(&trace)))
+}
+
+/// Same as `which_is_unsatisfied` for a new constraint system of `circuit`
+pub fn debug_circuit<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<Option<String>, PredicateError> {
+    which_is_unsatisfied(&synthesize_with_namespaces(circuit)?)
+}
+
+/// Namespaces of this crate in a trace formatted by `ConstraintTrace`. The trace has a line `<i>: <module>::<name>`
+/// for each namespace, innermost first, each followed by a line with its location. A constraint created outside
+/// any namespace has no trace, only its index, which is returned as it is.
+fn namespace_path(trace: &str) -> String {
+    let crate_name = module_path!().split("::").next().unwrap();
+    let mut names = trace
+        .lines()
+        .filter_map(|line| line.trim_start().split_once(": "))
+        .filter_map(|(_, path)| path.rsplit_once("::"))
+        .filter(|(module, _)| module.split("::").next() == Some(crate_name))
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        return trace.to_string();
+    }
+    names.reverse();
+    names.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::{BoundCheckCircuit, BoundMode};
+    use crate::sum::{LinearCombinationBoundCircuit, SumBoundCheckCircuit, SumCompareCircuit};
+    use crate::Fr;
+
+    fn frs(vals: &[u64]) -> Vec<Fr> {
+        vals.iter().map(|v| Fr::from(*v)).collect()
+    }
+
+    /// Change the only witness equal to `from` to `to` so that the constraints using it are not satisfied
+    fn tamper(cs: &ConstraintSystemRef<Fr>, from: u64, to: u64) {
+        let mut cs = cs.borrow_mut().unwrap();
+        let mut positions = cs
+            .witness_assignment
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w == Fr::from(from))
+            .map(|(i, _)| i);
+        let i = positions.next().unwrap();
+        assert!(positions.next().is_none());
+        cs.witness_assignment[i] = Fr::from(to);
+    }
+
+    #[test]
+    fn bound_check_steps() {
+        let circuit = |value: u64, min: u64, bits: Option<usize>| BoundCheckCircuit {
+            min: Some(Fr::from(min)),
+            max: Some(Fr::from(107u64)),
+            value: Some(Fr::from(value)),
+            bits,
+            mode: BoundMode::default(),
+        };
+        let cases = [
+            (105, 100, Some(16), None),
+            (105, 100, None, None),
+            (300, 100, Some(8), Some("range check")),
+            (50, 100, Some(6), Some("lower bound/range check")),
+            (50, 10, Some(6), Some("upper bound/range check")),
+            (100, 100, Some(16), Some("lower bound/comparison")),
+            (107, 100, Some(16), Some("upper bound/comparison")),
+            (100, 100, None, Some("lower bound/comparison")),
+            (107, 100, None, Some("upper bound/comparison")),
+        ];
+        for (value, min, bits, expected) in cases {
+            assert_eq!(
+                debug_circuit(circuit(value, min, bits)).unwrap().as_deref(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn sum_bound_check_steps() {
+        let circuit = |values: &[u64], min: u64, bits: usize| SumBoundCheckCircuit {
+            min: Some(Fr::from(min)),
+            max: Some(Fr::from(310u64)),
+            count: values.len(),
+            values: Some(frs(values)),
+            bits,
+        };
+        let cases: [(&[u64], u64, usize, Option<&str>); 5] = [
+            (&[100, 205], 300, 16, None),
+            (&[100, 70000], 300, 16, Some("range checks")),
+            (&[10, 5], 300, 4, Some("bound range checks")),
+            (&[100, 211], 300, 16, Some("comparison/sum <= max")),
+            (&[100, 199], 300, 16, Some("comparison/sum >= min")),
+        ];
+        for (values, min, bits, expected) in cases {
+            assert_eq!(
+                debug_circuit(circuit(values, min, bits))
+                    .unwrap()
+                    .as_deref(),
+                expected
+            );
+        }
+
+        // The sum is computed by the prover so a wrong sum breaks the summation
+        let cs = synthesize_with_namespaces(circuit(&[100, 205], 300, 16)).unwrap();
+        tamper(&cs, 305, 306);
+        assert_eq!(
+            which_is_unsatisfied(&cs).unwrap().as_deref(),
+            Some("summation")
+        );
+    }
+
+    #[test]
+    fn sum_compare_steps() {
+        let circuit = |smalls: &[u64], larges: &[u64]| SumCompareCircuit {
+            smalls_count: smalls.len(),
+            larges_count: larges.len(),
+            smalls: Some(frs(smalls)),
+            larges: Some(frs(larges)),
+            bits: 16,
+        };
+        let cases: [(&[u64], &[u64], Option<&str>); 3] = [
+            (&[1000, 10], &[1011, 1], None),
+            (&[70000, 10], &[1011, 1], Some("range checks")),
+            (&[1000, 10], &[1009, 1], Some("comparison")),
+        ];
+        for (smalls, larges, expected) in cases {
+            assert_eq!(
+                debug_circuit(circuit(smalls, larges)).unwrap().as_deref(),
+                expected
+            );
+        }
+
+        for (from, to, expected) in [
+            (1010, 1009, "summation/smalls"),
+            (1012, 1013, "summation/larges"),
+        ] {
+            let cs = synthesize_with_namespaces(circuit(&[1000, 10], &[1011, 1])).unwrap();
+            tamper(&cs, from, to);
+            assert_eq!(
+                which_is_unsatisfied(&cs).unwrap().as_deref(),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn linear_combination_bound_check_steps() {
+        let circuit =
+            |coefficients: &[u64], values: &[u64], min: u64| LinearCombinationBoundCircuit {
+                min: Some(Fr::from(min)),
+                max: Some(Fr::from(310u64)),
+                count: values.len(),
+                coefficients: Some(frs(coefficients)),
+                values: Some(frs(values)),
+                bits: 16,
+                coefficient_bits: 4,
+            };
+        // Coefficients, values, min and the expected failing step
+        type Case<'a> = (&'a [u64], &'a [u64], u64, Option<&'a str>);
+        let cases: [Case; 6] = [
+            (&[2, 1], &[100, 105], 300, None),
+            (&[2, 1], &[100, 70000], 300, Some("range checks")),
+            (&[16, 1], &[10, 150], 300, Some("coefficient range checks")),
+            (&[2, 1], &[100, 105], 3000000, Some("bound range checks")),
+            (&[2, 1], &[100, 111], 300, Some("comparison/sum <= max")),
+            (&[2, 1], &[100, 99], 300, Some("comparison/sum >= min")),
+        ];
+        for (coefficients, values, min, expected) in cases {
+            assert_eq!(
+                debug_circuit(circuit(coefficients, values, min))
+                    .unwrap()
+                    .as_deref(),
+                expected
+            );
+        }
+
+        // The product of a coefficient and a value is computed by the prover
+        let cs = synthesize_with_namespaces(circuit(&[2, 1], &[100, 105], 300)).unwrap();
+        tamper(&cs, 200, 201);
+        assert_eq!(
+            which_is_unsatisfied(&cs).unwrap().as_deref(),
+            Some("summation")
+        );
+    }
+}
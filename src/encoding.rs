@@ -0,0 +1,381 @@
+use crate::error::PredicateError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt;
+use ark_std::string::String;
+use ark_std::vec::Vec;
+use serde::de::{DeserializeOwned, Error as _, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// NOTE: Arkworks types like field and group elements and LegoGroth16 proofs are encoded using their compressed
+// `CanonicalSerialize` encoding. This is a base64url string without padding in human readable formats like
+// JSON and a byte string in binary formats like CBOR. Other types use their natural serde encoding.
+
+/// Serialize `value` as JSON
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, PredicateError> {
+    serde_json::to_string(value).map_err(PredicateError::Json)
+}
+
+/// Deserialize a value serialized with `to_json`
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, PredicateError> {
+    serde_json::from_str(json).map_err(PredicateError::Json)
+}
+
+/// Serialize `value` as CBOR
+pub fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, PredicateError> {
+    serde_cbor::to_vec(value).map_err(PredicateError::Cbor)
+}
+
+/// Deserialize a value serialized with `to_cbor`
+pub fn from_cbor<T: DeserializeOwned>(cbor: &[u8]) -> Result<T, PredicateError> {
+    serde_cbor::from_slice(cbor).map_err(PredicateError::Cbor)
+}
+
+/// For `#[serde(with = "crate::encoding::ark")]` on fields of arkworks types
+pub mod ark {
+    use super::*;
+
+    pub fn serialize<T: CanonicalSerialize, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ArkRef(value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: CanonicalDeserialize, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        <Ark<T> as Deserialize>::deserialize(deserializer).map(|a| a.0)
+    }
+}
+
+/// For `#[serde(with = "crate::encoding::ark_vec")]` on fields of type `Vec<T>` where `T` is an arkworks type
+pub mod ark_vec {
+    use super::*;
+
+    pub fn serialize<T: CanonicalSerialize, S: Serializer>(
+        values: &[T],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(ArkRef))
+    }
+
+    pub fn deserialize<'de, T: CanonicalDeserialize, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<T>, D::Error> {
+        <Vec<Ark<T>> as Deserialize>::deserialize(deserializer)
+            .map(|v| v.into_iter().map(|a| a.0).collect())
+    }
+}
+
+/// For `#[serde(with = "crate::encoding::ark_vec_vec")]` on fields of type `Vec<Vec<T>>` where `T` is an
+/// arkworks type
+pub mod ark_vec_vec {
+    use super::*;
+
+    pub fn serialize<T: CanonicalSerialize, S: Serializer>(
+        values: &[Vec<T>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            values
+                .iter()
+                .map(|v| v.iter().map(ArkRef).collect::<Vec<_>>()),
+        )
+    }
+
+    pub fn deserialize<'de, T: CanonicalDeserialize, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<T>>, D::Error> {
+        <Vec<Vec<Ark<T>>> as Deserialize>::deserialize(deserializer).map(|v| {
+            v.into_iter()
+                .map(|v| v.into_iter().map(|a| a.0).collect())
+                .collect()
+        })
+    }
+}
+
+/// Serde wrapper for an arkworks type, encoded as with `ark`. For values without serde support which the verifier
+/// receives along with a presentation, eg. the `SignatureParamsG1` and `PublicKeyG2` of an issuer or a LegoGroth16
+/// `VerifyingKey`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ark<T>(pub T);
+
+struct ArkRef<'a, T>(&'a T);
+
+impl<T: CanonicalSerialize> Serialize for Ark<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ArkRef(&self.0).serialize(serializer)
+    }
+}
+
+impl<'a, T: CanonicalSerialize> Serialize for ArkRef<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::with_capacity(self.0.serialized_size());
+        CanonicalSerialize::serialize(self.0, &mut bytes)
+            .map_err(|e| serde::ser::Error::custom(format!("{:?}", e)))?;
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD))
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+}
+
+impl<'de, T: CanonicalDeserialize> Deserialize<'de> for Ark<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = if deserializer.is_human_readable() {
+            let s = <String as Deserialize>::deserialize(deserializer)?;
+            let bytes =
+                base64::decode_config(&s, base64::URL_SAFE_NO_PAD).map_err(D::Error::custom)?;
+            // Reject padding and other non-canonical encodings so that each value has a single encoding
+            if base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD) != s {
+                return Err(D::Error::custom("non-canonical base64url"));
+            }
+            bytes
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)?
+        };
+        let mut reader = &bytes[..];
+        let value =
+            T::deserialize(&mut reader).map_err(|e| D::Error::custom(format!("{:?}", e)))?;
+        if !reader.is_empty() {
+            return Err(D::Error::custom("trailing bytes"));
+        }
+        Ok(Ark(value))
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::{Bound, BoundMode};
+    use crate::presentation::{
+        Credential, Predicate, PredicateProver, PredicateVerifier, Presentation,
+    };
+    use crate::tests::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use bbs_plus::prelude::{PublicKeyG2, SignatureParamsG1};
+    use legogroth16::VerifyingKey;
+    use std::fs;
+
+    const PREDICATES_JSON: &str = include_str!("../test_vectors/predicates.json");
+    const PREDICATES_CBOR: &[u8] = include_bytes!("../test_vectors/predicates.cbor");
+
+    const PRESENTATION_JSON: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_vectors/presentation.json"
+    );
+    const PRESENTATION_CBOR: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_vectors/presentation.cbor"
+    );
+
+    type Issuer = (
+        Ark<SignatureParamsG1<Bls12_381>>,
+        Ark<PublicKeyG2<Bls12_381>>,
+    );
+
+    /// What the verifier receives, for `predicates()` proved with `NONCE`
+    #[derive(Serialize, Deserialize)]
+    struct PresentationVector {
+        issuers: Vec<Issuer>,
+        verifying_keys: Vec<Ark<VerifyingKey<Bls12_381>>>,
+        predicates: Vec<Predicate>,
+        presentation: Presentation,
+    }
+
+    fn predicates() -> Vec<Predicate> {
+        vec![
+            Predicate::Bound {
+                message: (0, 2),
+                min: Fr::from(100u64),
+                max: Fr::from(110u64),
+                bits: Some(16),
+                mode: BoundMode::Both {
+                    lower: Bound::Strict,
+                    upper: Bound::Inclusive,
+                },
+            },
+            Predicate::SumBound {
+                messages: vec![(0, 1), (1, 3)],
+                min: Fr::from(300u64),
+                max: Fr::from(310u64),
+                bits: 16,
+            },
+            Predicate::NotEqualPublic {
+                message: (1, 0),
+                public: Fr::from(1000u64),
+            },
+            Predicate::Age {
+                birth_date: (0, 4),
+                today: Fr::from(20221018u64),
+                min_age: Fr::from(18u64),
+            },
+        ]
+    }
+
+    #[test]
+    fn predicates_test_vectors() {
+        assert_eq!(to_json(&predicates()).unwrap(), PREDICATES_JSON.trim_end());
+        assert_eq!(to_cbor(&predicates()).unwrap(), PREDICATES_CBOR);
+
+        let decoded = from_json::<Vec<Predicate>>(PREDICATES_JSON).unwrap();
+        assert!(matches!(
+            decoded[0],
+            Predicate::Bound {
+                message: (0, 2),
+                bits: Some(16),
+                ..
+            }
+        ));
+        assert_eq!(decoded[3].public_inputs(), predicates()[3].public_inputs());
+        assert_eq!(to_json(&decoded).unwrap(), PREDICATES_JSON.trim_end());
+
+        let decoded = from_cbor::<Vec<Predicate>>(PREDICATES_CBOR).unwrap();
+        assert_eq!(decoded[1].public_inputs(), predicates()[1].public_inputs());
+        assert_eq!(to_cbor(&decoded).unwrap(), PREDICATES_CBOR);
+
+        // Field elements must be unpadded base64url of exactly 32 bytes encoding a value less than the modulus
+        for bad in [
+            "ZAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+            "ZAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+            "ZAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+            "__________________________________________8",
+        ] {
+            let json = PREDICATES_JSON.replace("ZAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA", bad);
+            assert!(from_json::<Vec<Predicate>>(&json).is_err());
+        }
+    }
+
+    #[test]
+    fn presentation_json_and_cbor() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let (messages, sig_params, keypair, sig) = sig_setup(&mut rng, 5);
+        let credentials = vec![Credential {
+            signature: sig,
+            messages,
+            params: sig_params.clone(),
+            public_key: keypair.public_key.clone(),
+        }];
+
+        let bound = Predicate::Bound {
+            message: (0, 1),
+            min: Fr::from(100u64),
+            max: Fr::from(110u64),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        let bound_pk = bound.generate_proving_key(&mut rng).unwrap();
+
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(bound.clone(), &bound_pk);
+        let presentation = prover.prove(&mut rng, NONCE).unwrap();
+
+        // Verifier receives the predicate and the presentation
+        let json = to_json(&(bound.clone(), presentation.clone())).unwrap();
+        let cbor = to_cbor(&(bound, presentation)).unwrap();
+        assert!(cbor.len() < json.len());
+
+        for (predicate, presentation) in [
+            from_json::<(Predicate, Presentation)>(&json).unwrap(),
+            from_cbor::<(Predicate, Presentation)>(&cbor).unwrap(),
+        ] {
+            let mut verifier =
+                PredicateVerifier::new(vec![(sig_params.clone(), keypair.public_key.clone())]);
+            verifier.add_predicate(predicate, &bound_pk.vk);
+            verifier.verify(&presentation, NONCE).unwrap();
+        }
+
+        assert!(from_cbor::<(Predicate, Presentation)>(&cbor[..cbor.len() - 1]).is_err());
+        assert!(from_json::<(Predicate, Presentation)>(&json[..json.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn presentation_test_vectors() {
+        let missing = "missing test vector, generate it with \
+            `cargo test --features serde -- --ignored write_presentation_test_vectors`";
+        let json = fs::read_to_string(PRESENTATION_JSON).expect(missing);
+        let cbor = fs::read(PRESENTATION_CBOR).expect(missing);
+
+        for vector in [
+            from_json::<PresentationVector>(&json).unwrap(),
+            from_cbor::<PresentationVector>(&cbor).unwrap(),
+        ] {
+            let issuers = vector
+                .issuers
+                .iter()
+                .map(|(params, public_key)| (params.0.clone(), public_key.0.clone()))
+                .collect();
+            let mut verifier = PredicateVerifier::new(issuers);
+            for (predicate, vk) in vector.predicates.iter().zip(vector.verifying_keys.iter()) {
+                verifier.add_predicate(predicate.clone(), &vk.0);
+            }
+            verifier.verify(&vector.presentation, NONCE).unwrap();
+            assert!(verifier
+                .verify(&vector.presentation, b"other nonce")
+                .is_err());
+
+            assert_eq!(to_json(&vector).unwrap(), json.trim_end());
+            assert_eq!(to_cbor(&vector).unwrap(), cbor);
+        }
+    }
+
+    /// Write the vectors checked by `presentation_test_vectors`, with a fixed seed so that they only change when
+    /// the encoding or the proofs do
+    #[test]
+    #[ignore]
+    fn write_presentation_test_vectors() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        // Messages (0, 2) = 103, (0, 1) + (1, 3) = 306, (1, 0) = 201 and (0, 4) is the birth date for `predicates()`
+        let credentials = [
+            credential(
+                &mut rng,
+                [101u64, 102, 103, 104, 20041018]
+                    .into_iter()
+                    .map(Fr::from)
+                    .collect(),
+            ),
+            credential(&mut rng, (201..=205u64).map(Fr::from).collect()),
+        ];
+        let (presentation, verifying_keys) =
+            prove_predicates(&mut rng, &credentials, &predicates()).unwrap();
+        let vector = PresentationVector {
+            issuers: credentials
+                .iter()
+                .map(|c| (Ark(c.params.clone()), Ark(c.public_key.clone())))
+                .collect(),
+            verifying_keys: verifying_keys.into_iter().map(Ark).collect(),
+            predicates: predicates(),
+            presentation,
+        };
+        fs::write(PRESENTATION_JSON, to_json(&vector).unwrap() + "\n").unwrap();
+        fs::write(PRESENTATION_CBOR, to_cbor(&vector).unwrap()).unwrap();
+    }
+}
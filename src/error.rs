@@ -0,0 +1,273 @@
+use crate::crs::CrsError;
+use crate::presentation::MessageRef;
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::SerializationError;
+use ark_std::fmt;
+use ark_std::string::String;
+use bbs_plus::error::BBSPlusError;
+use proof_system::error::ProofSystemError;
+
+#[derive(Debug)]
+pub enum PredicateError {
+    /// The referenced credential or message does not exist
+    InvalidMessageRef(MessageRef),
+    /// A value needed by the circuit was not given
+    MissingAssignment,
+    /// The message does not fit in the bit-size of the values of the predicate
+    ValueOutOfRange {
+        message: MessageRef,
+        bits: usize,
+    },
+    /// Coefficient at this index of a linear combination is not a non-negative integer of `bits` bits, eg. a
+    /// negative weight encoded as `p - w`
+    CoefficientOutOfRange {
+        index: usize,
+        bits: usize,
+    },
+    /// Bit-size of the values of a predicate is more than the circuit can compare without wrapping around the
+    /// field's modulus
+    UnsupportedBitSize {
+        bits: usize,
+        max: usize,
+    },
+    /// The values given to a circuit do not satisfy it. Describes the first unsatisfied check, eg.
+    /// "value 107 is not < max 107".
+    UnsatisfiedWitness(String),
+    /// Number of values given for the committed witnesses of a circuit differs from the number it commits
+    CommitmentSizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// Number of SNARK proofs in the presentation differs from the number of predicates
+    IncorrectNumberOfSnarkProofs {
+        expected: usize,
+        found: usize,
+    },
+    /// Credentials, committed messages or public inputs of the presentation differ from the verifier's
+    PresentationMismatch,
+    /// Verifying key is not for a circuit with the expected number of public inputs and committed witnesses
+    IncompatibleVerifyingKey {
+        expected: usize,
+        found: usize,
+    },
+    /// Key file is truncated, cannot be decoded or is for another kind of key
+    InvalidKeyFile,
+    /// Key is labelled with the ID of a circuit other than the expected one
+    CircuitIdMismatch,
+    /// Contribution at this index of the ceremony transcript has an invalid proof of knowledge or was not made to
+    /// the parameters before it
+    InvalidContribution(usize),
+    /// Ceremony parameters are not derived from the initial parameters by the contributions in the transcript
+    CeremonyParametersMismatch,
+    /// Powers of tau from phase 1 are malformed or too few for the circuit. Names the field at fault.
+    InvalidPowersOfTau(&'static str),
+    /// Proving key and verifying key are not a well-formed pair
+    InvalidCrs(CrsError),
+    /// The message is not a member of the set or, for non-membership, is a member or outside the sentinels
+    NoSetPath,
+    /// Values cannot make a Merkle tree, eg. there are none or more than the depth of the tree allows
+    InvalidSet(String),
+    SynthesisError(SynthesisError),
+    LegoGroth16Error(legogroth16::error::Error),
+    ProofSystemError(ProofSystemError),
+    BBSPlusError(BBSPlusError),
+    Serialization(SerializationError),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    #[cfg(feature = "serde")]
+    Cbor(serde_cbor::Error),
+}
+
+impl fmt::Display for PredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMessageRef((c, m)) => {
+                write!(f, "message {} of credential {} does not exist", m, c)
+            }
+            Self::MissingAssignment => write!(f, "a value needed by the circuit was not given"),
+            Self::ValueOutOfRange {
+                message: (c, m),
+                bits,
+            } => write!(
+                f,
+                "message {} of credential {} does not fit in {} bits",
+                m, c, bits
+            ),
+            Self::CoefficientOutOfRange { index, bits } => write!(
+                f,
+                "coefficient {} is not a non-negative integer of {} bits",
+                index, bits
+            ),
+            Self::UnsupportedBitSize { bits, max } => write!(
+                f,
+                "bit-size {} is more than the maximum of {}",
+                bits, max
+            ),
+            Self::UnsatisfiedWitness(msg) => write!(f, "{}", msg),
+            Self::CommitmentSizeMismatch { expected, found } => write!(
+                f,
+                "expected {} committed witnesses, found {}",
+                expected, found
+            ),
+            Self::IncorrectNumberOfSnarkProofs { expected, found } => {
+                write!(f, "expected {} SNARK proofs, found {}", expected, found)
+            }
+            Self::PresentationMismatch => write!(
+                f,
+                "credentials, committed messages or public inputs of the presentation differ from the verifier's"
+            ),
+            Self::IncompatibleVerifyingKey { expected, found } => write!(
+                f,
+                "expected a verifying key with {} elements in gamma_abc_g1, found {}",
+                expected, found
+            ),
+            Self::InvalidKeyFile => write!(
+                f,
+                "key file is truncated, cannot be decoded or is for another kind of key"
+            ),
+            Self::CircuitIdMismatch => write!(f, "key is for another circuit"),
+            Self::InvalidContribution(index) => {
+                write!(f, "contribution {} of the ceremony is invalid", index)
+            }
+            Self::CeremonyParametersMismatch => write!(
+                f,
+                "ceremony parameters are not derived from the initial parameters by the contributions"
+            ),
+            Self::InvalidPowersOfTau(field) => write!(
+                f,
+                "powers of tau are malformed or too few for the circuit in {}",
+                field
+            ),
+            Self::InvalidCrs(e) => write!(f, "proving and verifying keys are malformed: {}", e),
+            Self::NoSetPath => write!(
+                f,
+                "message is not a member of the set or, for non-membership, is a member or outside the sentinels"
+            ),
+            Self::InvalidSet(msg) => write!(f, "invalid set: {}", msg),
+            Self::SynthesisError(e) => write!(f, "constraint synthesis failed: {}", e),
+            Self::LegoGroth16Error(e) => write!(f, "LegoGroth16 error: {:?}", e),
+            Self::ProofSystemError(e) => write!(f, "proof system error: {:?}", e),
+            Self::BBSPlusError(e) => write!(f, "BBS+ error: {:?}", e),
+            Self::Serialization(e) => write!(f, "serialization failed: {}", e),
+            #[cfg(feature = "std")]
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => write!(f, "JSON error: {}", e),
+            #[cfg(feature = "serde")]
+            Self::Cbor(e) => write!(f, "CBOR error: {}", e),
+        }
+    }
+}
+
+/// The source is the wrapped error, for the variants wrapping one which implements `Error`
+#[cfg(feature = "std")]
+impl std::error::Error for PredicateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidCrs(e) => Some(e),
+            Self::SynthesisError(e) => Some(e),
+            Self::Serialization(e) => Some(e),
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Self::Json(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Self::Cbor(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CrsError> for PredicateError {
+    fn from(e: CrsError) -> Self {
+        Self::InvalidCrs(e)
+    }
+}
+
+impl From<SynthesisError> for PredicateError {
+    fn from(e: SynthesisError) -> Self {
+        match e {
+            SynthesisError::AssignmentMissing => Self::MissingAssignment,
+            e => Self::SynthesisError(e),
+        }
+    }
+}
+
+impl From<legogroth16::error::Error> for PredicateError {
+    fn from(e: legogroth16::error::Error) -> Self {
+        Self::LegoGroth16Error(e)
+    }
+}
+
+impl From<ProofSystemError> for PredicateError {
+    fn from(e: ProofSystemError) -> Self {
+        Self::ProofSystemError(e)
+    }
+}
+
+impl From<BBSPlusError> for PredicateError {
+    fn from(e: BBSPlusError) -> Self {
+        Self::BBSPlusError(e)
+    }
+}
+
+impl From<SerializationError> for PredicateError {
+    fn from(e: SerializationError) -> Self {
+        Self::Serialization(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for PredicateError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::{BoundCheckCircuit, BoundMode};
+    use crate::tests::*;
+    use std::error::Error;
+
+    #[test]
+    fn error_messages() {
+        let circuit = BoundCheckCircuit {
+            min: Some(Fr::from(100u64)),
+            max: Some(Fr::from(107u64)),
+            value: Some(Fr::from(107u64)),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        let e = circuit.check_witness().unwrap_err();
+        assert_eq!(e.to_string(), "value 107 is not < max 107");
+        assert!(e.source().is_none());
+
+        assert_eq!(
+            PredicateError::ValueOutOfRange {
+                message: (1, 3),
+                bits: 16
+            }
+            .to_string(),
+            "message 3 of credential 1 does not fit in 16 bits"
+        );
+        assert_eq!(
+            PredicateError::from(CrsError::Pairing("delta_g1")).to_string(),
+            "proving and verifying keys are malformed: delta_g1 fails its pairing check"
+        );
+
+        // Wrapped errors are the source
+        let e = PredicateError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(e.to_string(), "I/O error: entity not found");
+        let source = e
+            .source()
+            .unwrap()
+            .downcast_ref::<std::io::Error>()
+            .unwrap();
+        assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        let e = PredicateError::from(SynthesisError::Unsatisfiable);
+        assert!(e.source().unwrap().is::<SynthesisError>());
+    }
+}
@@ -0,0 +1,377 @@
+use crate::bounds::BoundMode;
+use crate::error::PredicateError;
+use crate::presentation::{MessageRef, Predicate, PredicateVerifier, Presentation};
+use crate::Fr;
+use ark_bls12_381::Bls12_381;
+use ark_serialize::CanonicalDeserialize;
+use bbs_plus::prelude::{PublicKeyG2, SignatureParamsG1};
+use legogroth16::VerifyingKey;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+// NOTE: Keys and presentations are created from their compressed `CanonicalSerialize` encoding as opaque handles
+// which the caller must release with the matching `_free` function. Handles are written through an out pointer,
+// set to null on failure, and every other function returns a `BbsStatus`. Panics are caught so that they never
+// unwind into the caller. The header `include/bbs_predicate.h` is generated from this file by cbindgen, see
+// `build.rs`.
+
+/// Result of a call. Each variant of `PredicateError` has its own code.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BbsStatus {
+    Ok = 0,
+    /// A pointer argument is null
+    NullPointer = 1,
+    /// The library panicked, which is a bug
+    Panic = 2,
+    InvalidMessageRef = 10,
+    MissingAssignment = 11,
+    ValueOutOfRange = 12,
+    UnsatisfiedWitness = 13,
+    CommitmentSizeMismatch = 14,
+    IncorrectNumberOfSnarkProofs = 15,
+    PresentationMismatch = 16,
+    IncompatibleVerifyingKey = 17,
+    InvalidKeyFile = 18,
+    CircuitIdMismatch = 19,
+    InvalidContribution = 20,
+    CeremonyParametersMismatch = 21,
+    InvalidCrs = 22,
+    NoSetPath = 23,
+    SynthesisError = 24,
+    LegoGroth16Error = 25,
+    ProofSystemError = 26,
+    BBSPlusError = 27,
+    Serialization = 28,
+    Io = 29,
+    Json = 30,
+    Cbor = 31,
+    UnsupportedBitSize = 32,
+    CoefficientOutOfRange = 33,
+    InvalidSet = 34,
+    InvalidPowersOfTau = 35,
+}
+
+/// LegoGroth16 verifying key of a predicate's circuit
+pub struct BbsVerifyingKey(VerifyingKey<Bls12_381>);
+
+/// BBS+ public key of an issuer along with the signature params used with it
+pub struct BbsPublicKey {
+    params: SignatureParamsG1<Bls12_381>,
+    public_key: PublicKeyG2<Bls12_381>,
+}
+
+/// Presentation to verify
+pub struct BbsPresentation(Presentation);
+
+/// Reference to message `message` of credential `credential`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BbsMessageRef {
+    pub credential: usize,
+    pub message: usize,
+}
+
+/// `min < message < max` where each bound can be inclusive, see `Predicate::Bound`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BbsBoundCheck {
+    pub message: BbsMessageRef,
+    pub min: u64,
+    pub max: u64,
+    /// Bit-size of the values, 0 to compare them as field elements
+    pub bits: usize,
+    pub min_inclusive: bool,
+    pub max_inclusive: bool,
+}
+
+/// Sum of `smalls` < sum of `larges`, see `Predicate::SumCompare`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BbsSumCompare {
+    pub smalls: *const BbsMessageRef,
+    pub smalls_len: usize,
+    pub larges: *const BbsMessageRef,
+    pub larges_len: usize,
+    pub bits: usize,
+}
+
+impl From<PredicateError> for BbsStatus {
+    fn from(e: PredicateError) -> Self {
+        match e {
+            PredicateError::InvalidMessageRef(_) => Self::InvalidMessageRef,
+            PredicateError::MissingAssignment => Self::MissingAssignment,
+            PredicateError::ValueOutOfRange { .. } => Self::ValueOutOfRange,
+            PredicateError::CoefficientOutOfRange { .. } => Self::CoefficientOutOfRange,
+            PredicateError::UnsupportedBitSize { .. } => Self::UnsupportedBitSize,
+            PredicateError::UnsatisfiedWitness(_) => Self::UnsatisfiedWitness,
+            PredicateError::CommitmentSizeMismatch { .. } => Self::CommitmentSizeMismatch,
+            PredicateError::IncorrectNumberOfSnarkProofs { .. } => {
+                Self::IncorrectNumberOfSnarkProofs
+            }
+            PredicateError::PresentationMismatch => Self::PresentationMismatch,
+            PredicateError::IncompatibleVerifyingKey { .. } => Self::IncompatibleVerifyingKey,
+            PredicateError::InvalidKeyFile => Self::InvalidKeyFile,
+            PredicateError::CircuitIdMismatch => Self::CircuitIdMismatch,
+            PredicateError::InvalidContribution(_) => Self::InvalidContribution,
+            PredicateError::CeremonyParametersMismatch => Self::CeremonyParametersMismatch,
+            PredicateError::InvalidPowersOfTau(_) => Self::InvalidPowersOfTau,
+            PredicateError::InvalidCrs(_) => Self::InvalidCrs,
+            PredicateError::NoSetPath => Self::NoSetPath,
+            PredicateError::InvalidSet(_) => Self::InvalidSet,
+            PredicateError::SynthesisError(_) => Self::SynthesisError,
+            PredicateError::LegoGroth16Error(_) => Self::LegoGroth16Error,
+            PredicateError::ProofSystemError(_) => Self::ProofSystemError,
+            PredicateError::BBSPlusError(_) => Self::BBSPlusError,
+            PredicateError::Serialization(_) => Self::Serialization,
+            PredicateError::Io(_) => Self::Io,
+            #[cfg(feature = "serde")]
+            PredicateError::Json(_) => Self::Json,
+            #[cfg(feature = "serde")]
+            PredicateError::Cbor(_) => Self::Cbor,
+        }
+    }
+}
+
+/// Create a verifying key from its serialization. The key must be released with `bbs_verifying_key_free`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes and `out` to writable memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_verifying_key_from_bytes(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut BbsVerifyingKey,
+) -> BbsStatus {
+    call(|| write_handle(out, || Ok(BbsVerifyingKey(deserialize(bytes, len)?))))
+}
+
+/// Release a verifying key. Does nothing if `key` is null.
+///
+/// # Safety
+///
+/// `key` must be null or a key created by `bbs_verifying_key_from_bytes` which has not been released.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_verifying_key_free(key: *mut BbsVerifyingKey) {
+    free(key)
+}
+
+/// Create an issuer's public key from the serializations of its signature params and public key. The key must be
+/// released with `bbs_public_key_free`.
+///
+/// # Safety
+///
+/// `params` and `public_key` must point to `params_len` and `public_key_len` readable bytes and `out` to writable
+/// memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_public_key_from_bytes(
+    params: *const u8,
+    params_len: usize,
+    public_key: *const u8,
+    public_key_len: usize,
+    out: *mut *mut BbsPublicKey,
+) -> BbsStatus {
+    call(|| {
+        write_handle(out, || {
+            Ok(BbsPublicKey {
+                params: deserialize(params, params_len)?,
+                public_key: deserialize(public_key, public_key_len)?,
+            })
+        })
+    })
+}
+
+/// Release a public key. Does nothing if `key` is null.
+///
+/// # Safety
+///
+/// `key` must be null or a key created by `bbs_public_key_from_bytes` which has not been released.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_public_key_free(key: *mut BbsPublicKey) {
+    free(key)
+}
+
+/// Create a presentation from its serialization. The presentation must be released with `bbs_presentation_free`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes and `out` to writable memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_presentation_from_bytes(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut BbsPresentation,
+) -> BbsStatus {
+    call(|| write_handle(out, || Ok(BbsPresentation(deserialize(bytes, len)?))))
+}
+
+/// Release a presentation. Does nothing if `presentation` is null.
+///
+/// # Safety
+///
+/// `presentation` must be null or a presentation created by `bbs_presentation_from_bytes` which has not been
+/// released.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_presentation_free(presentation: *mut BbsPresentation) {
+    free(presentation)
+}
+
+/// Verify that `presentation` proves the bound check over credentials of `issuers`, given in the order of the
+/// credentials, and was created with the verifier's `nonce`. Returns `Ok` iff the presentation is valid.
+///
+/// # Safety
+///
+/// `presentation`, `verifying_key` and `predicate` must be valid pointers, `issuers` must point to
+/// `issuer_count` valid pointers and `nonce` to `nonce_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_verify_bound_check(
+    presentation: *const BbsPresentation,
+    issuers: *const *const BbsPublicKey,
+    issuer_count: usize,
+    verifying_key: *const BbsVerifyingKey,
+    predicate: *const BbsBoundCheck,
+    nonce: *const u8,
+    nonce_len: usize,
+) -> BbsStatus {
+    call(|| {
+        let predicate = predicate.as_ref().ok_or(BbsStatus::NullPointer)?;
+        let predicate = Predicate::Bound {
+            message: predicate.message.into(),
+            min: Fr::from(predicate.min),
+            max: Fr::from(predicate.max),
+            bits: (predicate.bits != 0).then_some(predicate.bits),
+            mode: BoundMode::both(predicate.min_inclusive, predicate.max_inclusive),
+        };
+        verify(
+            presentation,
+            issuers,
+            issuer_count,
+            verifying_key,
+            predicate,
+            as_slice(nonce, nonce_len)?,
+        )
+    })
+}
+
+/// Verify that `presentation` proves the sum comparison over credentials of `issuers`, given in the order of the
+/// credentials, and was created with the verifier's `nonce`. Returns `Ok` iff the presentation is valid.
+///
+/// # Safety
+///
+/// `presentation`, `verifying_key` and `predicate` must be valid pointers, `issuers` must point to
+/// `issuer_count` valid pointers, `nonce` to `nonce_len` readable bytes and the arrays of `predicate` must have the
+/// given lengths.
+#[no_mangle]
+pub unsafe extern "C" fn bbs_verify_sum_compare(
+    presentation: *const BbsPresentation,
+    issuers: *const *const BbsPublicKey,
+    issuer_count: usize,
+    verifying_key: *const BbsVerifyingKey,
+    predicate: *const BbsSumCompare,
+    nonce: *const u8,
+    nonce_len: usize,
+) -> BbsStatus {
+    call(|| {
+        let predicate = predicate.as_ref().ok_or(BbsStatus::NullPointer)?;
+        let message_refs =
+            |refs: *const BbsMessageRef, len| -> Result<Vec<MessageRef>, BbsStatus> {
+                Ok(as_slice(refs, len)?.iter().map(|m| (*m).into()).collect())
+            };
+        let predicate = Predicate::SumCompare {
+            smalls: message_refs(predicate.smalls, predicate.smalls_len)?,
+            larges: message_refs(predicate.larges, predicate.larges_len)?,
+            bits: predicate.bits,
+        };
+        verify(
+            presentation,
+            issuers,
+            issuer_count,
+            verifying_key,
+            predicate,
+            as_slice(nonce, nonce_len)?,
+        )
+    })
+}
+
+impl From<BbsMessageRef> for MessageRef {
+    fn from(m: BbsMessageRef) -> Self {
+        (m.credential, m.message)
+    }
+}
+
+/// Run `f`, turning a panic into `BbsStatus::Panic`
+fn call(f: impl FnOnce() -> Result<(), BbsStatus>) -> BbsStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => BbsStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => BbsStatus::Panic,
+    }
+}
+
+unsafe fn verify(
+    presentation: *const BbsPresentation,
+    issuers: *const *const BbsPublicKey,
+    issuer_count: usize,
+    verifying_key: *const BbsVerifyingKey,
+    predicate: Predicate,
+    nonce: &[u8],
+) -> Result<(), BbsStatus> {
+    let presentation = presentation.as_ref().ok_or(BbsStatus::NullPointer)?;
+    let verifying_key = verifying_key.as_ref().ok_or(BbsStatus::NullPointer)?;
+    let issuers = as_slice(issuers, issuer_count)?
+        .iter()
+        .map(|key| {
+            let key = key.as_ref().ok_or(BbsStatus::NullPointer)?;
+            Ok((key.params.clone(), key.public_key.clone()))
+        })
+        .collect::<Result<Vec<_>, BbsStatus>>()?;
+    let mut verifier = PredicateVerifier::new(issuers);
+    verifier.add_predicate(predicate, &verifying_key.0);
+    Ok(verifier.verify(&presentation.0, nonce)?)
+}
+
+/// `len` values at `values` which can be null if `len` is 0
+unsafe fn as_slice<'a, T>(values: *const T, len: usize) -> Result<&'a [T], BbsStatus> {
+    if len == 0 {
+        Ok(&[])
+    } else if values.is_null() {
+        Err(BbsStatus::NullPointer)
+    } else {
+        Ok(slice::from_raw_parts(values, len))
+    }
+}
+
+unsafe fn deserialize<T: CanonicalDeserialize>(
+    bytes: *const u8,
+    len: usize,
+) -> Result<T, BbsStatus> {
+    let mut bytes = as_slice(bytes, len)?;
+    let value = T::deserialize(&mut bytes).map_err(PredicateError::from)?;
+    // Trailing bytes mean that the value is not what the caller thinks it is
+    if !bytes.is_empty() {
+        return Err(
+            PredicateError::Serialization(ark_serialize::SerializationError::InvalidData).into(),
+        );
+    }
+    Ok(value)
+}
+
+/// Write a handle to the value created by `f` to `out`, or null if `f` fails
+unsafe fn write_handle<T>(
+    out: *mut *mut T,
+    f: impl FnOnce() -> Result<T, BbsStatus>,
+) -> Result<(), BbsStatus> {
+    if out.is_null() {
+        return Err(BbsStatus::NullPointer);
+    }
+    ptr::write(out, ptr::null_mut());
+    ptr::write(out, Box::into_raw(Box::new(f()?)));
+    Ok(())
+}
+
+unsafe fn free<T>(handle: *mut T) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
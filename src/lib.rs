@@ -1,24 +1,56 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod age;
 pub mod bounds;
+pub mod ceremony;
+pub mod circuit_id;
+pub mod commitment;
+pub mod crs;
+#[cfg(feature = "std")]
+pub mod debug;
+#[cfg(feature = "serde")]
+pub mod encoding;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod merkle;
+pub mod not_equal;
+pub mod poseidon;
+pub mod presentation;
+pub mod ratio;
+pub mod set;
+#[cfg(feature = "std")]
+pub mod store;
 pub mod sum;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use ark_bls12_381::{Bls12_381, G1Affine};
+use ark_ec::PairingEngine;
+use blake2::Blake2b;
+use proof_system::prelude::Proof;
 
-use ark_std::vec::Vec;
+pub type Fr = <Bls12_381 as PairingEngine>::Fr;
+pub type ProofG1 = Proof<Bls12_381, G1Affine, Fr, Blake2b>;
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, G1Affine, G1Projective};
-    use ark_ec::{PairingEngine, ProjectiveCurve};
+    use crate::error::PredicateError;
+    use crate::presentation::{
+        Credential, Predicate, PredicateProver, PredicateVerifier, Presentation,
+    };
+    pub use crate::{Fr, ProofG1};
     use ark_std::rand::RngCore;
     use bbs_plus::prelude::{KeypairG2, SignatureG1, SignatureParamsG1};
-    use blake2::Blake2b;
-    use proof_system::prelude::Proof;
+    use legogroth16::VerifyingKey;
     pub use proof_system::statement::{
         PedersenCommitment as PedersenCommitmentStmt, PoKBBSSignatureG1 as PoKSignatureBBSG1Stmt,
     };
     pub use proof_system::witness::PoKBBSSignatureG1 as PoKSignatureBBSG1Wit;
 
-    pub type Fr = <Bls12_381 as PairingEngine>::Fr;
-    pub type ProofG1 = Proof<Bls12_381, G1Affine, Fr, Blake2b>;
+    /// Nonce the verifier gives to the prover in tests
+    pub const NONCE: &[u8] = b"verifier nonce";
 
     // Generate messages, public params and signature
     pub fn sig_setup<R: RngCore>(
@@ -32,13 +64,77 @@ pub mod tests {
     ) {
         // Generate messages as 101, 102, ..., 100+ message_count
         let messages: Vec<Fr> = (1..=message_count)
-            .into_iter()
             .map(|i| Fr::from(100 + i as u64))
             .collect();
-        let params = SignatureParamsG1::<Bls12_381>::generate_using_rng(rng, message_count);
+        sig_setup_with_messages(rng, messages)
+    }
+
+    // Generate public params and signature over the given messages
+    pub fn sig_setup_with_messages<R: RngCore>(
+        rng: &mut R,
+        messages: Vec<Fr>,
+    ) -> (
+        Vec<Fr>,
+        SignatureParamsG1<Bls12_381>,
+        KeypairG2<Bls12_381>,
+        SignatureG1<Bls12_381>,
+    ) {
+        let params = SignatureParamsG1::<Bls12_381>::generate_using_rng(rng, messages.len());
         let keypair = KeypairG2::<Bls12_381>::generate_using_rng(rng, &params);
         let sig =
             SignatureG1::<Bls12_381>::new(rng, &messages, &keypair.secret_key, &params).unwrap();
         (messages, params, keypair, sig)
     }
+
+    // Credential with a signature over the given messages
+    pub fn credential<R: RngCore>(rng: &mut R, messages: Vec<Fr>) -> Credential {
+        let (messages, params, keypair, signature) = sig_setup_with_messages(rng, messages);
+        Credential {
+            signature,
+            messages,
+            params,
+            public_key: keypair.public_key,
+        }
+    }
+
+    // Presentation of the predicates over the credentials, with a new proving key for each predicate, along with
+    // the verifying keys
+    pub fn prove_predicates<R: RngCore>(
+        rng: &mut R,
+        credentials: &[Credential],
+        predicates: &[Predicate],
+    ) -> Result<(Presentation, Vec<VerifyingKey<Bls12_381>>), PredicateError> {
+        let proving_keys = predicates
+            .iter()
+            .map(|p| p.generate_proving_key(rng))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut prover = PredicateProver::new(credentials);
+        for (predicate, proving_key) in predicates.iter().zip(proving_keys.iter()) {
+            prover.add_predicate(predicate.clone(), proving_key);
+        }
+        let presentation = prover.prove(rng, NONCE)?;
+        Ok((
+            presentation,
+            proving_keys.into_iter().map(|pk| pk.vk).collect(),
+        ))
+    }
+
+    // Verify the presentation of the predicates over credentials of the issuers of `credentials`
+    pub fn verify_predicates(
+        credentials: &[Credential],
+        predicates: &[Predicate],
+        verifying_keys: &[VerifyingKey<Bls12_381>],
+        presentation: &Presentation,
+    ) -> Result<(), PredicateError> {
+        let mut verifier = PredicateVerifier::new(
+            credentials
+                .iter()
+                .map(|c| (c.params.clone(), c.public_key.clone()))
+                .collect(),
+        );
+        for (predicate, verifying_key) in predicates.iter().zip(verifying_keys.iter()) {
+            verifier.add_predicate(predicate.clone(), verifying_key);
+        }
+        verifier.verify(presentation, NONCE)
+    }
 }
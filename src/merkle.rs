@@ -0,0 +1,380 @@
+use crate::error::PredicateError;
+use crate::poseidon::PoseidonParams;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::format;
+use ark_std::vec;
+use ark_std::vec::Vec;
+
+/// Merkle tree of fixed depth using Poseidon as the 2-to-1 hash. The leaves are the values themselves and
+/// when the number of values is not a power of 2, the last value is repeated to fill the tree.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "", try_from = "MerkleTreeLevels<F>"))]
+pub struct MerkleTree<F: PrimeField> {
+    /// Nodes of each level, leaves first and root last
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark_vec_vec"))]
+    levels: Vec<Vec<F>>,
+}
+
+/// Serialized form of `MerkleTree`, checked to have the shape of a tree before becoming one. The hashes are
+/// not checked as the tree does not know its `PoseidonParams`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(bound = "")]
+struct MerkleTreeLevels<F: PrimeField> {
+    #[serde(with = "crate::encoding::ark_vec_vec")]
+    levels: Vec<Vec<F>>,
+}
+
+#[cfg(feature = "serde")]
+impl<F: PrimeField> TryFrom<MerkleTreeLevels<F>> for MerkleTree<F> {
+    type Error = String;
+
+    fn try_from(MerkleTreeLevels { levels }: MerkleTreeLevels<F>) -> Result<Self, String> {
+        let depth = match levels.len().checked_sub(1) {
+            Some(depth) if depth < usize::BITS as usize => depth,
+            _ => return Err(format!("a Merkle tree cannot have {} levels", levels.len())),
+        };
+        for (i, level) in levels.iter().enumerate() {
+            if level.len() != 1 << (depth - i) {
+                return Err(format!(
+                    "level {} of a Merkle tree of depth {} has {} nodes",
+                    i,
+                    depth,
+                    level.len()
+                ));
+            }
+        }
+        Ok(Self { levels })
+    }
+}
+
+/// Path from a leaf to the root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath<F: PrimeField> {
+    /// Index of the leaf. Its `i`th bit (from least significant) is set if the node at level `i` is the right
+    /// child of its parent.
+    pub index: usize,
+    /// Siblings of the nodes on the path, from the leaf's sibling to the root's child's sibling
+    pub siblings: Vec<F>,
+}
+
+impl<F: PrimeField> MerkleTree<F> {
+    /// Build a tree of the smallest depth that can hold `values`. Fails if `values` is empty.
+    pub fn new(params: &PoseidonParams<F>, values: &[F]) -> Result<Self, PredicateError> {
+        let size = values.len().next_power_of_two();
+        Self::new_with_depth(params, values, size.trailing_zeros() as usize)
+    }
+
+    /// Build a tree of depth `depth`, i.e. with `2^depth` leaves. Fails if `values` is empty or there are more
+    /// than `2^depth` values.
+    pub fn new_with_depth(
+        params: &PoseidonParams<F>,
+        values: &[F],
+        depth: usize,
+    ) -> Result<Self, PredicateError> {
+        if values.is_empty() {
+            return Err(PredicateError::InvalidSet("the set is empty".into()));
+        }
+        if depth >= usize::BITS as usize || values.len() > 1 << depth {
+            return Err(PredicateError::InvalidSet(format!(
+                "{} values do not fit in a tree of depth {}",
+                values.len(),
+                depth
+            )));
+        }
+        let mut leaves = values.to_vec();
+        leaves.resize(1 << depth, *values.last().unwrap());
+
+        let mut levels = vec![leaves];
+        for _ in 0..depth {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|c| params.hash(c[0], c[1]))
+                .collect();
+            levels.push(next);
+        }
+        Ok(Self { levels })
+    }
+
+    /// Build a tree whose leaves are `values` sorted in ascending order along with the sentinels `0` and
+    /// `2^bits - 1`, so that any value in `(0, 2^bits - 1)` not in `values` lies between 2 adjacent leaves.
+    /// Fails if any value does not fit in `bits` bits.
+    pub fn new_sorted(
+        params: &PoseidonParams<F>,
+        values: &[F],
+        bits: usize,
+    ) -> Result<Self, PredicateError> {
+        if bits >= F::size_in_bits() {
+            return Err(PredicateError::UnsupportedBitSize {
+                bits,
+                max: F::size_in_bits() - 1,
+            });
+        }
+        let upper_sentinel = F::from(2u64).pow([bits as u64]) - F::one();
+        if values.iter().any(|v| *v > upper_sentinel) {
+            return Err(PredicateError::InvalidSet(format!(
+                "a value does not fit in {} bits",
+                bits
+            )));
+        }
+        let mut leaves = values.to_vec();
+        leaves.push(F::zero());
+        leaves.push(upper_sentinel);
+        leaves.sort();
+        leaves.dedup();
+        Self::new(params, &leaves)
+    }
+
+    pub fn root(&self) -> F {
+        self.levels.last().unwrap()[0]
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// The leaves including the padding
+    pub fn leaves(&self) -> &[F] {
+        &self.levels[0]
+    }
+
+    /// Index of the first leaf with the given value
+    pub fn index_of(&self, value: &F) -> Option<usize> {
+        self.leaves().iter().position(|l| l == value)
+    }
+
+    /// Path of the leaf at `index`. Panics if `index` is out of range.
+    pub fn path(&self, index: usize) -> MerklePath<F> {
+        assert!(index < self.leaves().len());
+        let siblings = self.levels[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(i, level)| level[(index >> i) ^ 1])
+            .collect();
+        MerklePath { index, siblings }
+    }
+
+    /// Path of the leaf with the given value, if present
+    pub fn membership_path(&self, value: &F) -> Option<MerklePath<F>> {
+        self.index_of(value).map(|i| self.path(i))
+    }
+
+    /// For a tree created with `new_sorted`, the paths of the adjacent leaves `lower` and `upper` such that
+    /// `lower < value < upper`. `None` if `value` is a leaf or outside the sentinels.
+    pub fn non_membership_paths(&self, value: &F) -> Option<(MerklePath<F>, MerklePath<F>)> {
+        self.leaves()
+            .windows(2)
+            .position(|w| w[0] < *value && *value < w[1])
+            .map(|i| (self.path(i), self.path(i + 1)))
+    }
+}
+
+impl<F: PrimeField> MerklePath<F> {
+    /// Compute the root of the tree from the leaf and this path
+    pub fn root(&self, params: &PoseidonParams<F>, leaf: F) -> F {
+        let mut current = leaf;
+        for (i, sibling) in self.siblings.iter().enumerate() {
+            current = if (self.index >> i) & 1 == 1 {
+                params.hash(*sibling, current)
+            } else {
+                params.hash(current, *sibling)
+            };
+        }
+        current
+    }
+}
+
+/// Path allocated as witnesses in a circuit
+pub struct MerklePathVar<F: PrimeField> {
+    /// Bits of the leaf index, least significant first
+    pub index_bits: Vec<Boolean<F>>,
+    pub siblings: Vec<FpVar<F>>,
+}
+
+impl<F: PrimeField> MerklePathVar<F> {
+    /// Allocate a path of the given depth as witnesses. `path` is `None` during setup. Fails with `Unsatisfiable`
+    /// if `path` is for a tree of another depth.
+    pub fn new_witness(
+        cs: ConstraintSystemRef<F>,
+        path: Option<&MerklePath<F>>,
+        depth: usize,
+    ) -> Result<Self, SynthesisError> {
+        if path.is_some_and(|p| p.siblings.len() != depth) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let mut index_bits = Vec::with_capacity(depth);
+        let mut siblings = Vec::with_capacity(depth);
+        for i in 0..depth {
+            index_bits.push(Boolean::new_witness(cs.clone(), || {
+                path.map(|p| (p.index >> i) & 1 == 1)
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?);
+            siblings.push(FpVar::new_witness(cs.clone(), || {
+                path.map(|p| p.siblings[i])
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?);
+        }
+        Ok(Self {
+            index_bits,
+            siblings,
+        })
+    }
+
+    /// Compute the root of the tree from the leaf and this path
+    pub fn root(
+        &self,
+        params: &PoseidonParams<F>,
+        leaf: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut current = leaf.clone();
+        for (is_right, sibling) in self.index_bits.iter().zip(self.siblings.iter()) {
+            let left = FpVar::conditionally_select(is_right, sibling, &current)?;
+            let right = FpVar::conditionally_select(is_right, &current, sibling)?;
+            current = params.hash_gadget(&left, &right)?;
+        }
+        Ok(current)
+    }
+
+    /// The leaf index as a field element
+    pub fn index(&self) -> Result<FpVar<F>, SynthesisError> {
+        Boolean::le_bits_to_fp_var(&self.index_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn tree_and_paths() {
+        let params = PoseidonParams::<Fr>::new();
+
+        let values = (1..=5u64).map(Fr::from).collect::<Vec<_>>();
+        let tree = MerkleTree::new(&params, &values).unwrap();
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.leaves().len(), 8);
+        // Padded with the last value
+        assert_eq!(tree.leaves()[7], Fr::from(5u64));
+
+        for (i, v) in values.iter().enumerate() {
+            let path = tree.membership_path(v).unwrap();
+            assert_eq!(path.index, i);
+            assert_eq!(path.root(&params, *v), tree.root());
+            assert_ne!(path.root(&params, Fr::from(10u64)), tree.root());
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let leaf = FpVar::new_witness(cs.clone(), || Ok(*v)).unwrap();
+            let path_var = MerklePathVar::new_witness(cs.clone(), Some(&path), 3).unwrap();
+            assert_eq!(
+                path_var.root(&params, &leaf).unwrap().value().unwrap(),
+                tree.root()
+            );
+            assert_eq!(
+                path_var.index().unwrap().value().unwrap(),
+                Fr::from(i as u64)
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+        assert!(tree.membership_path(&Fr::from(6u64)).is_none());
+
+        // Sentinels 0 and 255 are added and the leaves are sorted
+        let sorted = MerkleTree::new_sorted(
+            &params,
+            &[Fr::from(40u64), Fr::from(10u64), Fr::from(30u64)],
+            8,
+        )
+        .unwrap();
+        assert_eq!(
+            sorted.leaves(),
+            &[0, 10, 30, 40, 255, 255, 255, 255]
+                .iter()
+                .map(|i| Fr::from(*i as u64))
+                .collect::<Vec<_>>()[..]
+        );
+        let (lower, upper) = sorted.non_membership_paths(&Fr::from(20u64)).unwrap();
+        assert_eq!((lower.index, upper.index), (1, 2));
+        let (lower, upper) = sorted.non_membership_paths(&Fr::from(5u64)).unwrap();
+        assert_eq!((lower.index, upper.index), (0, 1));
+        let (lower, upper) = sorted.non_membership_paths(&Fr::from(41u64)).unwrap();
+        assert_eq!((lower.index, upper.index), (3, 4));
+        assert!(sorted.non_membership_paths(&Fr::from(30u64)).is_none());
+        assert!(sorted.non_membership_paths(&Fr::from(255u64)).is_none());
+        assert!(sorted.non_membership_paths(&Fr::from(0u64)).is_none());
+
+        let single = MerkleTree::new(&params, &values[..1]).unwrap();
+        assert_eq!(single.depth(), 0);
+        assert_eq!(single.root(), values[0]);
+        assert_eq!(single.path(0).root(&params, values[0]), values[0]);
+    }
+
+    #[test]
+    fn invalid_trees_and_paths() {
+        let params = PoseidonParams::<Fr>::new();
+        let values = (1..=5u64).map(Fr::from).collect::<Vec<_>>();
+
+        assert!(matches!(
+            MerkleTree::new(&params, &[]),
+            Err(PredicateError::InvalidSet(_))
+        ));
+        assert!(matches!(
+            MerkleTree::new_with_depth(&params, &values, 2),
+            Err(PredicateError::InvalidSet(_))
+        ));
+        assert!(matches!(
+            MerkleTree::new_with_depth(&params, &values, usize::BITS as usize),
+            Err(PredicateError::InvalidSet(_))
+        ));
+        assert!(matches!(
+            MerkleTree::new_sorted(&params, &[Fr::from(256u64)], 8),
+            Err(PredicateError::InvalidSet(_))
+        ));
+        assert!(matches!(
+            MerkleTree::new_sorted(&params, &values, 255),
+            Err(PredicateError::UnsupportedBitSize { .. })
+        ));
+
+        // Deserialized trees must have the shape of a tree
+        #[cfg(feature = "serde")]
+        {
+            use crate::encoding::{from_json, to_json};
+            let tree = MerkleTree::new(&params, &values).unwrap();
+            let json = to_json(&tree).unwrap();
+            assert_eq!(
+                from_json::<MerkleTree<Fr>>(&json).unwrap().root(),
+                tree.root()
+            );
+            let levels =
+                serde_json::from_str::<serde_json::Value>(&json).unwrap()["levels"].clone();
+            let mut missing_leaf = levels.clone();
+            missing_leaf[0].as_array_mut().unwrap().pop();
+            let mut missing_level = levels.clone();
+            missing_level.as_array_mut().unwrap().remove(1);
+            for levels in [serde_json::json!([]), missing_leaf, missing_level] {
+                let json = serde_json::json!({ "levels": levels }).to_string();
+                assert!(matches!(
+                    from_json::<MerkleTree<Fr>>(&json),
+                    Err(PredicateError::Json(_))
+                ));
+            }
+        }
+
+        // Path of a tree of another depth
+        let tree = MerkleTree::new(&params, &values).unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(matches!(
+            MerklePathVar::new_witness(cs, Some(&tree.path(0)), 4),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::bounds::to_decimal;
+use crate::error::PredicateError;
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::format;
+
+/// Enforce value != public where `public` is a public input
+#[derive(Clone)]
+pub struct NotEqualPublicCircuit<F: Field> {
+    pub value: Option<F>,
+    pub public: Option<F>,
+}
+
+/// Enforce value_1 != value_2 where both are committed, `value_1` first
+#[derive(Clone)]
+pub struct NotEqualCircuit<F: Field> {
+    pub value_1: Option<F>,
+    pub value_2: Option<F>,
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
+    for NotEqualPublicCircuit<ConstraintF>
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let value = FpVar::new_variable(
+            cs.clone(),
+            || self.value.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let public = FpVar::new_variable(
+            cs.clone(),
+            || self.public.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+        value.enforce_not_equal(&public)
+    }
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for NotEqualCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let value_1 = FpVar::new_variable(
+            cs.clone(),
+            || self.value_1.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let value_2 = FpVar::new_variable(
+            cs.clone(),
+            || self.value_2.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        value_1.enforce_not_equal(&value_2)
+    }
+}
+
+impl<F: PrimeField> NotEqualPublicCircuit<F> {
+    /// Check natively that the values satisfy the circuit. Without it, proving with equal values fails with
+    /// `MissingAssignment` as the difference of the values has no inverse.
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        let value = self.value.ok_or(PredicateError::MissingAssignment)?;
+        let public = self.public.ok_or(PredicateError::MissingAssignment)?;
+        check_not_equal(("value", &value), ("public", &public))
+    }
+}
+
+impl<F: PrimeField> NotEqualCircuit<F> {
+    /// Check natively that the values satisfy the circuit, see `NotEqualPublicCircuit::check_witness`
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        let value_1 = self.value_1.ok_or(PredicateError::MissingAssignment)?;
+        let value_2 = self.value_2.ok_or(PredicateError::MissingAssignment)?;
+        check_not_equal(("value_1", &value_1), ("value_2", &value_2))
+    }
+}
+
+fn check_not_equal<F: PrimeField>(
+    (a_name, a): (&str, &F),
+    (b_name, b): (&str, &F),
+) -> Result<(), PredicateError> {
+    if a == b {
+        return Err(PredicateError::UnsatisfiedWitness(format!(
+            "{} {} is equal to {} {}",
+            a_name,
+            to_decimal(a),
+            b_name,
+            to_decimal(b)
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PredicateError;
+    use crate::presentation::Predicate;
+    use crate::tests::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn messages_not_equal() {
+        // Prover has 2 BBS+ signatures and he wants to prove that a signed message, like the status of the 1st
+        // credential, is not equal to a public value, like REVOKED, and that a signed message from the 1st
+        // signature is not equal to a signed message from the 2nd signature, without revealing any of them.
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let credentials = [
+            credential(&mut rng, (101..=105u64).map(Fr::from).collect()),
+            credential(&mut rng, (101..=110u64).map(Fr::from).collect()),
+        ];
+        let not_equal_public = |public: u64| Predicate::NotEqualPublic {
+            message: (0, 2),
+            public: Fr::from(public),
+        };
+        let not_equal = |message_2| Predicate::NotEqual {
+            message_1: (0, 1),
+            message_2,
+        };
+
+        // 103 != 1000 and 102 != 104
+        let predicates = [not_equal_public(1000), not_equal((1, 3))];
+        let (presentation, vks) = prove_predicates(&mut rng, &credentials, &predicates).unwrap();
+        verify_predicates(&credentials, &predicates, &vks, &presentation).unwrap();
+
+        // Presentation is for a specific public value
+        let other = [not_equal_public(103), not_equal((1, 3))];
+        assert!(matches!(
+            verify_predicates(&credentials, &other, &vks, &presentation),
+            Err(PredicateError::PresentationMismatch)
+        ));
+
+        // Equal values can't be proven
+        for (predicates, error) in [
+            ([not_equal_public(103)], "value 103 is equal to public 103"),
+            ([not_equal((1, 1))], "value_1 102 is equal to value_2 102"),
+        ] {
+            match prove_predicates(&mut rng, &credentials, &predicates) {
+                Err(PredicateError::UnsatisfiedWitness(e)) => assert_eq!(e, error),
+                r => panic!("unexpected {:?}", r.map(|_| ())),
+            }
+        }
+    }
+
+    // Synthesis fails when the values are equal as their difference has no inverse
+    fn is_satisfied<C: ConstraintSynthesizer<Fr>>(circuit: C) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).is_ok() && cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn not_equal_circuits() {
+        let circuit = |value_1: u64, value_2: u64| NotEqualCircuit {
+            value_1: Some(Fr::from(value_1)),
+            value_2: Some(Fr::from(value_2)),
+        };
+        assert!(is_satisfied(circuit(102, 104)));
+        assert!(!is_satisfied(circuit(102, 102)));
+        circuit(102, 104).check_witness().unwrap();
+        assert!(circuit(102, 102).check_witness().is_err());
+
+        let circuit = |value: u64, public: u64| NotEqualPublicCircuit {
+            value: Some(Fr::from(value)),
+            public: Some(Fr::from(public)),
+        };
+        assert!(is_satisfied(circuit(103, 1000)));
+        assert!(!is_satisfied(circuit(103, 103)));
+        circuit(103, 1000).check_witness().unwrap();
+        assert!(circuit(103, 103).check_witness().is_err());
+    }
+}
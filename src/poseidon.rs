@@ -0,0 +1,188 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_relations::r1cs::SynthesisError;
+use ark_std::vec::Vec;
+use blake2::{Blake2b, Digest};
+
+// NOTE: Poseidon permutation of width 3 with S-box x^5 used as a 2-to-1 hash for Merkle trees. x^5 is a
+// permutation over the scalar field of BLS12-381. The number of rounds is the one recommended for 128-bit
+// security with this width and S-box over a ~255-bit field. The round constants are derived from a versioned
+// label with Blake2b rather than sampled from an RNG, whose output may change with the version of `rand`, so that
+// Merkle roots published by issuers stay the same across dependency upgrades and can be reproduced by other
+// implementations.
+
+/// Size of the state, 2 elements for the inputs and 1 for capacity
+pub const WIDTH: usize = 3;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 57;
+
+/// Domain separator of the round constants. Constants derived any other way must use another label.
+pub const ROUND_CONSTANTS_LABEL: &[u8] = b"BBS-predicate-Poseidon-x5-3-round-constants-v1";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct PoseidonParams<F: PrimeField> {
+    /// `WIDTH` constants for each round
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark_vec"))]
+    pub round_constants: Vec<F>,
+    /// `WIDTH x WIDTH` MDS matrix
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark_vec_vec"))]
+    pub mds: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> PoseidonParams<F> {
+    /// The round constant at index `i` is `Blake2b(ROUND_CONSTANTS_LABEL || i)`, with `i` as 8 little-endian
+    /// bytes, reduced modulo the field's modulus. The MDS matrix is the Cauchy matrix `1 / (x_i + y_j)` with
+    /// `x_i = i` and `y_j = WIDTH + j`.
+    pub fn new() -> Self {
+        let round_constants = (0..(FULL_ROUNDS + PARTIAL_ROUNDS) * WIDTH)
+            .map(|i| {
+                let mut hasher = Blake2b::new();
+                hasher.update(ROUND_CONSTANTS_LABEL);
+                hasher.update((i as u64).to_le_bytes());
+                F::from_le_bytes_mod_order(&hasher.finalize())
+            })
+            .collect();
+        let mds = (0..WIDTH)
+            .map(|i| {
+                (0..WIDTH)
+                    .map(|j| F::from((i + WIDTH + j) as u64).inverse().unwrap())
+                    .collect()
+            })
+            .collect();
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+
+    /// Hash 2 field elements into 1
+    pub fn hash(&self, left: F, right: F) -> F {
+        let mut state = [left, right, F::zero()];
+        for r in 0..FULL_ROUNDS + PARTIAL_ROUNDS {
+            for (i, s) in state.iter_mut().enumerate() {
+                *s += self.round_constants[r * WIDTH + i];
+            }
+            if is_full_round(r) {
+                for s in state.iter_mut() {
+                    *s = sbox(*s);
+                }
+            } else {
+                state[0] = sbox(state[0]);
+            }
+            let mut new_state = [F::zero(); WIDTH];
+            for (n, row) in new_state.iter_mut().zip(&self.mds) {
+                for (m, s) in row.iter().zip(&state) {
+                    *n += *m * s;
+                }
+            }
+            state = new_state;
+        }
+        state[0]
+    }
+
+    /// Same as `hash` but in the circuit. Costs 3 constraints per S-box, so 243 constraints in total.
+    pub fn hash_gadget(
+        &self,
+        left: &FpVar<F>,
+        right: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut state = [left.clone(), right.clone(), FpVar::zero()];
+        for r in 0..FULL_ROUNDS + PARTIAL_ROUNDS {
+            for (i, s) in state.iter_mut().enumerate() {
+                *s += self.round_constants[r * WIDTH + i];
+            }
+            if is_full_round(r) {
+                for s in state.iter_mut() {
+                    *s = sbox_gadget(s)?;
+                }
+            } else {
+                state[0] = sbox_gadget(&state[0])?;
+            }
+            let mut new_state = [FpVar::zero(), FpVar::zero(), FpVar::zero()];
+            for (n, row) in new_state.iter_mut().zip(&self.mds) {
+                for (m, s) in row.iter().zip(&state) {
+                    *n += s * *m;
+                }
+            }
+            state = new_state;
+        }
+        let [out, _, _] = state;
+        Ok(out)
+    }
+}
+
+impl<F: PrimeField> Default for PoseidonParams<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Half the full rounds are at the beginning and the other half at the end
+fn is_full_round(r: usize) -> bool {
+    !(FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&r)
+}
+
+fn sbox<F: PrimeField>(x: F) -> F {
+    let x2 = x.square();
+    x2.square() * x
+}
+
+fn sbox_gadget<F: PrimeField>(x: &FpVar<F>) -> Result<FpVar<F>, SynthesisError> {
+    let x2 = x.square()?;
+    Ok(x2.square()? * x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::to_decimal;
+    use crate::tests::*;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::{
+        rand::{rngs::StdRng, SeedableRng},
+        UniformRand,
+    };
+
+    #[test]
+    fn hash_gadget_matches_native() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let params = PoseidonParams::<Fr>::new();
+
+        let left = Fr::rand(&mut rng);
+        let right = Fr::rand(&mut rng);
+        let expected = params.hash(left, right);
+        assert_ne!(expected, params.hash(right, left));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let left_var = FpVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_var = FpVar::new_witness(cs.clone(), || Ok(right)).unwrap();
+        let out = params.hash_gadget(&left_var, &right_var).unwrap();
+        assert_eq!(out.value().unwrap(), expected);
+        assert!(cs.is_satisfied().unwrap());
+        println!("Number of constraints for hash {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn known_answers() {
+        // Fixed outputs so that a change of the constants, and thus of every Merkle root, is caught
+        let params = PoseidonParams::<Fr>::new();
+        assert_eq!(params, PoseidonParams::default());
+        assert_eq!(
+            params.round_constants.len(),
+            (FULL_ROUNDS + PARTIAL_ROUNDS) * WIDTH
+        );
+        assert_eq!(
+            to_decimal(&params.round_constants[0]),
+            "49930178594442628996086307624644277489070197715890739820174297175844212601538"
+        );
+        assert_eq!(
+            to_decimal(&params.hash(Fr::from(1u64), Fr::from(2u64))),
+            "52372581003480831453528286933501455846354582712683247318175670714874465788584"
+        );
+    }
+}
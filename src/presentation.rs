@@ -0,0 +1,1177 @@
+use crate::age::{AgeCheckCircuit, DATE_BITS};
+use crate::bounds::{BoundCheckCircuit, BoundMode};
+use crate::commitment::{commitment_bases, commitment_opening};
+use crate::error::PredicateError;
+use crate::merkle::MerkleTree;
+use crate::not_equal::{NotEqualCircuit, NotEqualPublicCircuit};
+use crate::poseidon::PoseidonParams;
+use crate::ratio::RatioBoundCircuit;
+use crate::set::{SetMembershipCircuit, SetNonMembershipCircuit};
+use crate::sum::{LinearCombinationBoundCircuit, SumBoundCheckCircuit, SumCompareCircuit};
+use crate::{Fr, ProofG1};
+use ark_bls12_381::{Bls12_381, G1Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::collections::{BTreeMap, BTreeSet};
+use ark_std::io::{Read, Write};
+use ark_std::rand::RngCore;
+use ark_std::vec;
+use ark_std::vec::Vec;
+use ark_std::UniformRand;
+use bbs_plus::prelude::{PublicKeyG2, SignatureG1, SignatureParamsG1};
+use blake2::{Blake2b, Digest};
+use legogroth16::{
+    create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    ProvingKey, VerifyingKey,
+};
+use proof_system::prelude::{
+    EqualWitnesses, MetaStatement, MetaStatements, ProofSpec, Statement, Statements, Witness,
+    WitnessRef, Witnesses,
+};
+use proof_system::statement::{
+    PedersenCommitment as PedersenCommitmentStmt, PoKBBSSignatureG1 as PoKSignatureBBSG1Stmt,
+};
+use proof_system::witness::PoKBBSSignatureG1 as PoKSignatureBBSG1Wit;
+
+// NOTE: A presentation consists of 1 LegoGroth16 proof per predicate and a single composite proof. The composite
+// proof has 1 statement per credential proving knowledge of the BBS+ signature, followed by 1 Pedersen commitment
+// statement per predicate proving knowledge of the opening of the commitment `d` in the LegoGroth16 proof. The
+// committed messages are tied to the signed messages with witness equalities.
+
+/// Domain separator for the nonce of the composite proof
+const PRESENTATION_NONCE_LABEL: &[u8] = b"BBS-predicate-presentation";
+
+/// Magic bytes at the start of a serialized `Presentation`
+pub const PRESENTATION_MAGIC: [u8; 4] = *b"BBSP";
+/// Version of the serialization format of `Presentation`
+pub const PRESENTATION_VERSION: u8 = 1;
+
+/// Reference to a signed message as `(credential index, message index)`
+pub type MessageRef = (usize, usize);
+
+/// A predicate over signed messages. The messages are hidden and the other values are public.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Predicate {
+    /// `min < message < max` as per `mode`, see `BoundCheckCircuit`. The bound not checked by `mode` is ignored.
+    Bound {
+        message: MessageRef,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        min: Fr,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        max: Fr,
+        bits: Option<usize>,
+        mode: BoundMode,
+    },
+    /// `min <= sum of messages <= max`, see `SumBoundCheckCircuit`
+    SumBound {
+        messages: Vec<MessageRef>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        min: Fr,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        max: Fr,
+        bits: usize,
+    },
+    /// Sum of `smalls` < sum of `larges`, see `SumCompareCircuit`
+    SumCompare {
+        smalls: Vec<MessageRef>,
+        larges: Vec<MessageRef>,
+        bits: usize,
+    },
+    /// `min <= sum of coefficient * message <= max`, see `LinearCombinationBoundCircuit`. Coefficients must be
+    /// non-negative integers less than `2^coefficient_bits`, so negative weights are not supported.
+    LinearCombinationBound {
+        messages: Vec<MessageRef>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark_vec"))]
+        coefficients: Vec<Fr>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        min: Fr,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        max: Fr,
+        bits: usize,
+        coefficient_bits: usize,
+    },
+    /// `num / den <= p / q`, see `RatioBoundCircuit`
+    RatioBound {
+        num: MessageRef,
+        den: MessageRef,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        p: Fr,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        q: Fr,
+        bits: usize,
+    },
+    /// `message != public`
+    NotEqualPublic {
+        message: MessageRef,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        public: Fr,
+    },
+    /// `message_1 != message_2`
+    NotEqual {
+        message_1: MessageRef,
+        message_2: MessageRef,
+    },
+    /// Holder born on `birth_date` is at least `min_age` years old on `today`, see `AgeCheckCircuit`
+    Age {
+        birth_date: MessageRef,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        today: Fr,
+        #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+        min_age: Fr,
+    },
+    /// `message` is a leaf of `tree`
+    SetMembership {
+        message: MessageRef,
+        tree: MerkleTree<Fr>,
+        params: PoseidonParams<Fr>,
+    },
+    /// `message` is not a leaf of `tree` which must be created with `MerkleTree::new_sorted` using `bits`
+    SetNonMembership {
+        message: MessageRef,
+        tree: MerkleTree<Fr>,
+        bits: usize,
+        params: PoseidonParams<Fr>,
+    },
+}
+
+/// Circuit of any predicate
+#[derive(Clone)]
+pub enum PredicateCircuit {
+    Bound(BoundCheckCircuit<Fr>),
+    SumBound(SumBoundCheckCircuit<Fr>),
+    SumCompare(SumCompareCircuit<Fr>),
+    LinearCombinationBound(LinearCombinationBoundCircuit<Fr>),
+    RatioBound(RatioBoundCircuit<Fr>),
+    NotEqualPublic(NotEqualPublicCircuit<Fr>),
+    NotEqual(NotEqualCircuit<Fr>),
+    Age(AgeCheckCircuit<Fr>),
+    SetMembership(SetMembershipCircuit<Fr>),
+    SetNonMembership(SetNonMembershipCircuit<Fr>),
+}
+
+/// A BBS+ signature held by the prover along with the signed messages and the signer's public params and key
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Credential {
+    pub signature: SignatureG1<Bls12_381>,
+    pub messages: Vec<Fr>,
+    pub params: SignatureParamsG1<Bls12_381>,
+    pub public_key: PublicKeyG2<Bls12_381>,
+}
+
+/// Creates a `Presentation` proving several predicates over messages of several credentials
+pub struct PredicateProver<'a> {
+    credentials: &'a [Credential],
+    predicates: Vec<(Predicate, &'a ProvingKey<Bls12_381>)>,
+}
+
+/// Verifies a `Presentation` given the signers' public params and keys and the predicates
+pub struct PredicateVerifier<'a> {
+    issuers: Vec<(SignatureParamsG1<Bls12_381>, PublicKeyG2<Bls12_381>)>,
+    predicates: Vec<(Predicate, &'a VerifyingKey<Bls12_381>)>,
+}
+
+/// Proof of predicates over messages of several credentials. Serialized as `PRESENTATION_MAGIC`, followed by
+/// `PRESENTATION_VERSION`, 1 byte which is 1 for the uncompressed encoding and 0 otherwise, and then the fields.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Presentation {
+    /// Number of credentials, each having a signature statement in `proof`
+    pub credential_count: usize,
+    /// Messages committed in each LegoGroth16 proof, identifying the witness equalities of `proof`
+    pub committed_messages: Vec<Vec<MessageRef>>,
+    /// Public inputs of each LegoGroth16 proof
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark_vec_vec"))]
+    pub public_inputs: Vec<Vec<Fr>>,
+    /// LegoGroth16 proofs, in the order the predicates were added
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark_vec"))]
+    pub snark_proofs: Vec<legogroth16::Proof<Bls12_381>>,
+    /// Proof of knowledge of the signatures and of the openings of the commitments in `snark_proofs`
+    #[cfg_attr(feature = "serde", serde(with = "crate::encoding::ark"))]
+    pub proof: ProofG1,
+}
+
+impl Predicate {
+    /// Messages committed in the LegoGroth16 proof, in the order the circuit allocates them
+    pub fn committed_messages(&self) -> Vec<MessageRef> {
+        match self {
+            Self::Bound { message, .. }
+            | Self::NotEqualPublic { message, .. }
+            | Self::SetMembership { message, .. }
+            | Self::SetNonMembership { message, .. } => vec![*message],
+            Self::SumBound { messages, .. } | Self::LinearCombinationBound { messages, .. } => {
+                messages.clone()
+            }
+            Self::SumCompare { smalls, larges, .. } => {
+                smalls.iter().chain(larges.iter()).cloned().collect()
+            }
+            Self::RatioBound { num, den, .. } => vec![*num, *den],
+            Self::NotEqual {
+                message_1,
+                message_2,
+            } => vec![*message_1, *message_2],
+            Self::Age { birth_date, .. } => vec![*birth_date],
+        }
+    }
+
+    /// Public inputs of the circuit, in the order the circuit allocates them
+    pub fn public_inputs(&self) -> Vec<Fr> {
+        match self {
+            Self::Bound { min, max, mode, .. } => {
+                let mut inputs = vec![];
+                if mode.lower().is_some() {
+                    inputs.push(*min);
+                }
+                if mode.upper().is_some() {
+                    inputs.push(*max);
+                }
+                inputs
+            }
+            Self::SumBound { min, max, .. } => vec![*min, *max],
+            Self::SumCompare { .. } | Self::NotEqual { .. } => vec![],
+            Self::LinearCombinationBound {
+                coefficients,
+                min,
+                max,
+                ..
+            } => {
+                let mut inputs = vec![*min, *max];
+                inputs.extend_from_slice(coefficients);
+                inputs
+            }
+            Self::RatioBound { p, q, .. } => vec![*p, *q],
+            Self::NotEqualPublic { public, .. } => vec![*public],
+            Self::Age { today, min_age, .. } => vec![*today, *min_age],
+            Self::SetMembership { tree, .. } | Self::SetNonMembership { tree, .. } => {
+                vec![tree.root()]
+            }
+        }
+    }
+
+    /// The circuit of this predicate. `values` are the values of `committed_messages` and are `None` during setup.
+    pub fn circuit(&self, values: Option<Vec<Fr>>) -> Result<PredicateCircuit, PredicateError> {
+        if let Some(values) = &values {
+            let expected = self.committed_messages().len();
+            if values.len() != expected {
+                return Err(PredicateError::CommitmentSizeMismatch {
+                    expected,
+                    found: values.len(),
+                });
+            }
+        }
+        let value = |i: usize| values.as_ref().map(|v| v[i]);
+        let circuit = match self {
+            Self::Bound {
+                min,
+                max,
+                bits,
+                mode,
+                ..
+            } => PredicateCircuit::Bound(BoundCheckCircuit {
+                min: Some(*min),
+                max: Some(*max),
+                value: value(0),
+                bits: *bits,
+                mode: *mode,
+            }),
+            Self::SumBound {
+                messages,
+                min,
+                max,
+                bits,
+            } => PredicateCircuit::SumBound(SumBoundCheckCircuit {
+                min: Some(*min),
+                max: Some(*max),
+                count: messages.len(),
+                values,
+                bits: *bits,
+            }),
+            Self::SumCompare {
+                smalls,
+                larges,
+                bits,
+            } => {
+                let (small_values, large_values) = match values {
+                    Some(mut v) => {
+                        let l = v.split_off(smalls.len());
+                        (Some(v), Some(l))
+                    }
+                    None => (None, None),
+                };
+                PredicateCircuit::SumCompare(SumCompareCircuit {
+                    smalls_count: smalls.len(),
+                    larges_count: larges.len(),
+                    smalls: small_values,
+                    larges: large_values,
+                    bits: *bits,
+                })
+            }
+            Self::LinearCombinationBound {
+                messages,
+                coefficients,
+                min,
+                max,
+                bits,
+                coefficient_bits,
+            } => PredicateCircuit::LinearCombinationBound(LinearCombinationBoundCircuit {
+                min: Some(*min),
+                max: Some(*max),
+                count: messages.len(),
+                coefficients: Some(coefficients.clone()),
+                values,
+                bits: *bits,
+                coefficient_bits: *coefficient_bits,
+            }),
+            Self::RatioBound { p, q, bits, .. } => {
+                PredicateCircuit::RatioBound(RatioBoundCircuit {
+                    p: Some(*p),
+                    q: Some(*q),
+                    num: value(0),
+                    den: value(1),
+                    bits: *bits,
+                })
+            }
+            Self::NotEqualPublic { public, .. } => {
+                PredicateCircuit::NotEqualPublic(NotEqualPublicCircuit {
+                    value: value(0),
+                    public: Some(*public),
+                })
+            }
+            Self::NotEqual { .. } => PredicateCircuit::NotEqual(NotEqualCircuit {
+                value_1: value(0),
+                value_2: value(1),
+            }),
+            Self::Age { today, min_age, .. } => PredicateCircuit::Age(AgeCheckCircuit {
+                birth_date: value(0),
+                today: Some(*today),
+                min_age: Some(*min_age),
+            }),
+            Self::SetMembership { tree, params, .. } => {
+                let member = value(0);
+                let path = member
+                    .map(|m| tree.membership_path(&m).ok_or(PredicateError::NoSetPath))
+                    .transpose()?;
+                PredicateCircuit::SetMembership(SetMembershipCircuit {
+                    root: Some(tree.root()),
+                    member,
+                    path,
+                    depth: tree.depth(),
+                    params: params.clone(),
+                })
+            }
+            Self::SetNonMembership {
+                tree, bits, params, ..
+            } => {
+                let non_member = value(0);
+                let paths = non_member
+                    .map(|m| {
+                        tree.non_membership_paths(&m)
+                            .ok_or(PredicateError::NoSetPath)
+                    })
+                    .transpose()?;
+                let (lower_path, upper_path) = match paths {
+                    Some((l, u)) => (Some(l), Some(u)),
+                    None => (None, None),
+                };
+                PredicateCircuit::SetNonMembership(SetNonMembershipCircuit {
+                    root: Some(tree.root()),
+                    non_member,
+                    lower: lower_path.as_ref().map(|p| tree.leaves()[p.index]),
+                    lower_path,
+                    upper: upper_path.as_ref().map(|p| tree.leaves()[p.index]),
+                    upper_path,
+                    depth: tree.depth(),
+                    bits: *bits,
+                    params: params.clone(),
+                })
+            }
+        };
+        Ok(circuit)
+    }
+
+    /// Upper bound on the bit-size of each of `committed_messages`, if the circuit constrains it
+    pub fn value_bits(&self) -> Option<usize> {
+        match self {
+            Self::Bound { bits, .. } => *bits,
+            Self::SumBound { bits, .. }
+            | Self::SumCompare { bits, .. }
+            | Self::LinearCombinationBound { bits, .. }
+            | Self::RatioBound { bits, .. } => Some(*bits),
+            Self::Age { .. } => Some(DATE_BITS),
+            Self::NotEqualPublic { .. }
+            | Self::NotEqual { .. }
+            | Self::SetMembership { .. }
+            | Self::SetNonMembership { .. } => None,
+        }
+    }
+
+    /// Generate the LegoGroth16 proving key of this predicate's circuit. The same key can be used for any
+    /// predicate of the same kind and shape, i.e. differing only in the messages and public values.
+    pub fn generate_proving_key<R: RngCore>(
+        &self,
+        rng: &mut R,
+    ) -> Result<ProvingKey<Bls12_381>, PredicateError> {
+        let commit_witness_count = self.committed_messages().len();
+        Ok(generate_random_parameters::<Bls12_381, _, _>(
+            self.circuit(None)?,
+            commit_witness_count,
+            rng,
+        )?)
+    }
+}
+
+impl PredicateCircuit {
+    /// Check natively that the values satisfy the circuit, to fail before proving rather than create a proof
+    /// that fails verification
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        match self {
+            Self::Bound(c) => c.check_witness(),
+            Self::SumBound(c) => c.check_witness(),
+            Self::SumCompare(c) => c.check_witness(),
+            Self::LinearCombinationBound(c) => c.check_witness(),
+            Self::RatioBound(c) => c.check_witness(),
+            Self::NotEqualPublic(c) => c.check_witness(),
+            Self::NotEqual(c) => c.check_witness(),
+            Self::Age(c) => c.check_witness(),
+            Self::SetMembership(c) => c.check_witness(),
+            Self::SetNonMembership(c) => c.check_witness(),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for PredicateCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        match self {
+            Self::Bound(c) => c.generate_constraints(cs),
+            Self::SumBound(c) => c.generate_constraints(cs),
+            Self::SumCompare(c) => c.generate_constraints(cs),
+            Self::LinearCombinationBound(c) => c.generate_constraints(cs),
+            Self::RatioBound(c) => c.generate_constraints(cs),
+            Self::NotEqualPublic(c) => c.generate_constraints(cs),
+            Self::NotEqual(c) => c.generate_constraints(cs),
+            Self::Age(c) => c.generate_constraints(cs),
+            Self::SetMembership(c) => c.generate_constraints(cs),
+            Self::SetNonMembership(c) => c.generate_constraints(cs),
+        }
+    }
+}
+
+impl Credential {
+    /// Verify the signature on the messages
+    pub fn verify(&self) -> Result<(), PredicateError> {
+        self.signature
+            .verify(&self.messages, &self.public_key, &self.params)?;
+        Ok(())
+    }
+}
+
+impl<'a> PredicateProver<'a> {
+    pub fn new(credentials: &'a [Credential]) -> Self {
+        Self {
+            credentials,
+            predicates: vec![],
+        }
+    }
+
+    /// Add a predicate to be proved with the given proving key
+    pub fn add_predicate(&mut self, predicate: Predicate, proving_key: &'a ProvingKey<Bls12_381>) {
+        self.predicates.push((predicate, proving_key));
+    }
+
+    /// Fails without creating any proof if a signature is invalid or a message does not fit in the bit-size of a
+    /// predicate, as the proof would then fail verification. Fails before creating the proof of a predicate over
+    /// bounds or sums if the messages do not satisfy it. `nonce` is supplied by the verifier, eg. random bytes fresh
+    /// for each session, and the presentation only verifies with the same nonce so it cannot be replayed.
+    pub fn prove<R: RngCore>(
+        &self,
+        rng: &mut R,
+        nonce: &[u8],
+    ) -> Result<Presentation, PredicateError> {
+        for c in self.credentials {
+            c.verify()?;
+        }
+        for (predicate, _) in &self.predicates {
+            if let Some(bits) = predicate.value_bits() {
+                for m in predicate.committed_messages() {
+                    if self.message(m)?.into_repr().num_bits() as usize > bits {
+                        return Err(PredicateError::ValueOutOfRange { message: m, bits });
+                    }
+                }
+            }
+        }
+
+        let mut snark_proofs = Vec::with_capacity(self.predicates.len());
+        // Opening of the commitment in each LegoGroth16 proof, the committed messages followed by the randomness
+        let mut openings = Vec::with_capacity(self.predicates.len());
+        for (predicate, proving_key) in &self.predicates {
+            let values = predicate
+                .committed_messages()
+                .into_iter()
+                .map(|m| self.message(m))
+                .collect::<Result<Vec<_>, _>>()?;
+            let v = Fr::rand(rng);
+            let circuit = predicate.circuit(Some(values.clone()))?;
+            circuit.check_witness()?;
+            snark_proofs.push(create_random_proof(circuit, v, proving_key, rng)?);
+            openings.push(commitment_opening(&values, v));
+        }
+
+        let issuers = self
+            .credentials
+            .iter()
+            .map(|c| (&c.params, &c.public_key))
+            .collect::<Vec<_>>();
+        let predicates = self
+            .predicates
+            .iter()
+            .map(|(p, pk)| (p, &pk.vk))
+            .collect::<Vec<_>>();
+        let proof_spec = create_proof_spec(&issuers, &predicates, &snark_proofs)?;
+
+        let mut witnesses = Witnesses::new();
+        for c in self.credentials {
+            witnesses.add(PoKSignatureBBSG1Wit::new_as_witness(
+                c.signature.clone(),
+                c.messages.iter().cloned().enumerate().collect(),
+            ));
+        }
+        for opening in openings {
+            witnesses.add(Witness::PedersenCommitment(opening));
+        }
+
+        let nonce = snark_proofs_digest(nonce, &predicates, &snark_proofs)?;
+        let proof = ProofG1::new(rng, proof_spec, witnesses, Some(nonce))?;
+        Ok(Presentation {
+            credential_count: self.credentials.len(),
+            committed_messages: self
+                .predicates
+                .iter()
+                .map(|(p, _)| p.committed_messages())
+                .collect(),
+            public_inputs: self
+                .predicates
+                .iter()
+                .map(|(p, _)| p.public_inputs())
+                .collect(),
+            snark_proofs,
+            proof,
+        })
+    }
+
+    fn message(&self, (c_idx, m_idx): MessageRef) -> Result<Fr, PredicateError> {
+        self.credentials
+            .get(c_idx)
+            .and_then(|c| c.messages.get(m_idx))
+            .cloned()
+            .ok_or(PredicateError::InvalidMessageRef((c_idx, m_idx)))
+    }
+}
+
+impl<'a> PredicateVerifier<'a> {
+    /// `issuers` are the signature params and public key of each credential, in the order used by the prover
+    pub fn new(issuers: Vec<(SignatureParamsG1<Bls12_381>, PublicKeyG2<Bls12_381>)>) -> Self {
+        Self {
+            issuers,
+            predicates: vec![],
+        }
+    }
+
+    /// Add a predicate to be verified with the given verifying key, in the order used by the prover
+    pub fn add_predicate(
+        &mut self,
+        predicate: Predicate,
+        verifying_key: &'a VerifyingKey<Bls12_381>,
+    ) {
+        self.predicates.push((predicate, verifying_key));
+    }
+
+    /// `nonce` must be the one given to the prover for this presentation
+    pub fn verify(&self, presentation: &Presentation, nonce: &[u8]) -> Result<(), PredicateError> {
+        if presentation.snark_proofs.len() != self.predicates.len() {
+            return Err(PredicateError::IncorrectNumberOfSnarkProofs {
+                expected: self.predicates.len(),
+                found: presentation.snark_proofs.len(),
+            });
+        }
+        let committed_messages = self
+            .predicates
+            .iter()
+            .map(|(p, _)| p.committed_messages())
+            .collect::<Vec<_>>();
+        let public_inputs = self
+            .predicates
+            .iter()
+            .map(|(p, _)| p.public_inputs())
+            .collect::<Vec<_>>();
+        if presentation.credential_count != self.issuers.len()
+            || presentation.committed_messages != committed_messages
+            || presentation.public_inputs != public_inputs
+        {
+            return Err(PredicateError::PresentationMismatch);
+        }
+        for ((predicate, vk), snark_proof) in
+            self.predicates.iter().zip(presentation.snark_proofs.iter())
+        {
+            let pvk = prepare_verifying_key(vk);
+            verify_proof(&pvk, snark_proof, &predicate.public_inputs())?;
+        }
+
+        let issuers = self.issuers.iter().map(|(p, k)| (p, k)).collect::<Vec<_>>();
+        let predicates = self
+            .predicates
+            .iter()
+            .map(|(p, vk)| (p, *vk))
+            .collect::<Vec<_>>();
+        let proof_spec = create_proof_spec(&issuers, &predicates, &presentation.snark_proofs)?;
+        let nonce = snark_proofs_digest(nonce, &predicates, &presentation.snark_proofs)?;
+        presentation.proof.clone().verify(proof_spec, Some(nonce))?;
+        Ok(())
+    }
+}
+
+impl Presentation {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: bool,
+    ) -> Result<(), SerializationError> {
+        writer.write_all(&PRESENTATION_MAGIC)?;
+        writer.write_all(&[PRESENTATION_VERSION, !compress as u8])?;
+        write_field(&self.credential_count, &mut writer, compress)?;
+        write_field(&self.committed_messages, &mut writer, compress)?;
+        write_field(&self.public_inputs, &mut writer, compress)?;
+        write_field(&self.snark_proofs, &mut writer, compress)?;
+        write_field(&self.proof, &mut writer, compress)
+    }
+
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: bool,
+    ) -> Result<Self, SerializationError> {
+        let mut header = [0u8; 6];
+        reader.read_exact(&mut header)?;
+        if header[..4] != PRESENTATION_MAGIC
+            || header[4] != PRESENTATION_VERSION
+            || header[5] != !compress as u8
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(Self {
+            credential_count: read_field(&mut reader, compress)?,
+            committed_messages: read_field(&mut reader, compress)?,
+            public_inputs: read_field(&mut reader, compress)?,
+            snark_proofs: read_field(&mut reader, compress)?,
+            proof: read_field(&mut reader, compress)?,
+        })
+    }
+}
+
+impl CanonicalSerialize for Presentation {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.serialize_with_mode(writer, true)
+    }
+
+    fn serialized_size(&self) -> usize {
+        PRESENTATION_MAGIC.len()
+            + 2
+            + self.credential_count.serialized_size()
+            + self.committed_messages.serialized_size()
+            + self.public_inputs.serialized_size()
+            + self.snark_proofs.serialized_size()
+            + self.proof.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.serialize_with_mode(writer, false)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        PRESENTATION_MAGIC.len()
+            + 2
+            + self.credential_count.uncompressed_size()
+            + self.committed_messages.uncompressed_size()
+            + self.public_inputs.uncompressed_size()
+            + self.snark_proofs.uncompressed_size()
+            + self.proof.uncompressed_size()
+    }
+}
+
+impl CanonicalDeserialize for Presentation {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_with_mode(reader, true)
+    }
+
+    fn deserialize_uncompressed<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_with_mode(reader, false)
+    }
+}
+
+fn write_field<T: CanonicalSerialize, W: Write>(
+    field: &T,
+    writer: W,
+    compress: bool,
+) -> Result<(), SerializationError> {
+    if compress {
+        field.serialize(writer)
+    } else {
+        field.serialize_uncompressed(writer)
+    }
+}
+
+fn read_field<T: CanonicalDeserialize, R: Read>(
+    reader: R,
+    compress: bool,
+) -> Result<T, SerializationError> {
+    if compress {
+        T::deserialize(reader)
+    } else {
+        T::deserialize_uncompressed(reader)
+    }
+}
+
+/// Proof spec shared by the prover and verifier. Statement `i` is for the `i`th credential and statement
+/// `issuers.len() + j` for the commitment in the `j`th LegoGroth16 proof.
+fn create_proof_spec(
+    issuers: &[(&SignatureParamsG1<Bls12_381>, &PublicKeyG2<Bls12_381>)],
+    predicates: &[(&Predicate, &VerifyingKey<Bls12_381>)],
+    snark_proofs: &[legogroth16::Proof<Bls12_381>],
+) -> Result<ProofSpec<Bls12_381, G1Affine>, PredicateError> {
+    let mut statements = Statements::new();
+    for (params, public_key) in issuers {
+        statements.add(Statement::PoKBBSSignatureG1(PoKSignatureBBSG1Stmt {
+            params: (*params).clone(),
+            public_key: (*public_key).clone(),
+            revealed_messages: BTreeMap::new(),
+        }));
+    }
+
+    // All references to the same message must be in a single equality, else the equalities would not be disjoint
+    let mut equalities = BTreeMap::<MessageRef, BTreeSet<WitnessRef>>::new();
+    for (j, ((predicate, vk), snark_proof)) in predicates.iter().zip(snark_proofs).enumerate() {
+        let committed = predicate.committed_messages();
+        for (k, (c_idx, m_idx)) in committed.iter().enumerate() {
+            match issuers.get(*c_idx) {
+                Some((params, _)) if *m_idx < params.supported_message_count() => (),
+                _ => return Err(PredicateError::InvalidMessageRef((*c_idx, *m_idx))),
+            }
+            let refs = equalities.entry((*c_idx, *m_idx)).or_default();
+            refs.insert((*c_idx, *m_idx));
+            refs.insert((issuers.len() + j, k));
+        }
+        statements.add(Statement::PedersenCommitment(PedersenCommitmentStmt {
+            bases: commitment_bases(vk, predicate.public_inputs().len(), committed.len())?,
+            commitment: snark_proof.d,
+        }));
+    }
+
+    let mut meta_statements = MetaStatements::new();
+    for refs in equalities.into_values() {
+        meta_statements.add(MetaStatement::WitnessEquality(EqualWitnesses(refs)));
+    }
+
+    Ok(ProofSpec {
+        statements,
+        meta_statements,
+        context: None,
+    })
+}
+
+/// Hash of the verifier's nonce and the LegoGroth16 proofs along with their public inputs. This is used as the nonce
+/// of the composite proof so that it is bound to the verifier's nonce and to the whole of each LegoGroth16 proof and
+/// not just to the commitment `d`, thus a LegoGroth16 proof cannot be replaced by another, eg. one re-randomized or
+/// from another presentation.
+fn snark_proofs_digest(
+    nonce: &[u8],
+    predicates: &[(&Predicate, &VerifyingKey<Bls12_381>)],
+    snark_proofs: &[legogroth16::Proof<Bls12_381>],
+) -> Result<Vec<u8>, PredicateError> {
+    let mut bytes = PRESENTATION_NONCE_LABEL.to_vec();
+    (nonce.len() as u64).serialize(&mut bytes)?;
+    bytes.extend_from_slice(nonce);
+    (snark_proofs.len() as u64).serialize(&mut bytes)?;
+    for ((predicate, _), snark_proof) in predicates.iter().zip(snark_proofs) {
+        snark_proof.serialize(&mut bytes)?;
+        predicate.public_inputs().serialize(&mut bytes)?;
+    }
+    let mut hasher = Blake2b::new();
+    hasher.update(&bytes);
+    Ok(hasher.finalize().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::{Field, PrimeField};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn prove_and_verify_predicates() {
+        // Prover has 2 BBS+ signatures and proves a bound on a message of the 1st, a bound on the sum of a message
+        // from each, and that a message of the 2nd is not equal to a message of the 1st, in a single presentation.
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let (messages_1, sig_params_1, keypair_1, sig_1) = sig_setup(&mut rng, 5);
+        let (messages_2, sig_params_2, keypair_2, sig_2) =
+            sig_setup_with_messages(&mut rng, (1..=5u64).map(|i| Fr::from(200 + i)).collect());
+
+        let credentials = vec![
+            Credential {
+                signature: sig_1,
+                messages: messages_1,
+                params: sig_params_1.clone(),
+                public_key: keypair_1.public_key.clone(),
+            },
+            Credential {
+                signature: sig_2,
+                messages: messages_2,
+                params: sig_params_2.clone(),
+                public_key: keypair_2.public_key.clone(),
+            },
+        ];
+
+        // Message (0, 2) is 103 and used in 2 predicates, (1, 3) is 204
+        let bound = Predicate::Bound {
+            message: (0, 2),
+            min: Fr::from(100u64),
+            max: Fr::from(110u64),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        let sum_bound = Predicate::SumBound {
+            messages: vec![(0, 2), (1, 3)],
+            min: Fr::from(300u64),
+            max: Fr::from(310u64),
+            bits: 16,
+        };
+        let not_equal = Predicate::NotEqual {
+            message_1: (0, 2),
+            message_2: (1, 0),
+        };
+
+        let bound_pk = bound.generate_proving_key(&mut rng).unwrap();
+        let sum_bound_pk = sum_bound.generate_proving_key(&mut rng).unwrap();
+        let not_equal_pk = not_equal.generate_proving_key(&mut rng).unwrap();
+
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(bound.clone(), &bound_pk);
+        prover.add_predicate(sum_bound.clone(), &sum_bound_pk);
+        prover.add_predicate(not_equal.clone(), &not_equal_pk);
+        let presentation = prover.prove(&mut rng, NONCE).unwrap();
+
+        let issuers = vec![
+            (sig_params_1.clone(), keypair_1.public_key.clone()),
+            (sig_params_2.clone(), keypair_2.public_key.clone()),
+        ];
+        let mut verifier = PredicateVerifier::new(issuers.clone());
+        verifier.add_predicate(bound.clone(), &bound_pk.vk);
+        verifier.add_predicate(sum_bound.clone(), &sum_bound_pk.vk);
+        verifier.add_predicate(not_equal.clone(), &not_equal_pk.vk);
+        verifier.verify(&presentation, NONCE).unwrap();
+
+        // Verifier with a different nonce rejects
+        assert!(verifier.verify(&presentation, b"other nonce").is_err());
+        assert!(verifier.verify(&presentation, &[]).is_err());
+
+        // Verifier expecting different public values rejects
+        let mut verifier = PredicateVerifier::new(issuers.clone());
+        verifier.add_predicate(
+            Predicate::Bound {
+                message: (0, 2),
+                min: Fr::from(100u64),
+                max: Fr::from(103u64),
+                bits: Some(16),
+                mode: BoundMode::default(),
+            },
+            &bound_pk.vk,
+        );
+        verifier.add_predicate(sum_bound.clone(), &sum_bound_pk.vk);
+        verifier.add_predicate(not_equal.clone(), &not_equal_pk.vk);
+        assert!(verifier.verify(&presentation, NONCE).is_err());
+
+        // Verifier expecting the predicate on a different message rejects
+        let mut verifier = PredicateVerifier::new(issuers.clone());
+        verifier.add_predicate(bound.clone(), &bound_pk.vk);
+        verifier.add_predicate(
+            Predicate::SumBound {
+                messages: vec![(0, 2), (1, 4)],
+                min: Fr::from(300u64),
+                max: Fr::from(310u64),
+                bits: 16,
+            },
+            &sum_bound_pk.vk,
+        );
+        verifier.add_predicate(not_equal.clone(), &not_equal_pk.vk);
+        assert!(verifier.verify(&presentation, NONCE).is_err());
+
+        // Verifier expecting fewer predicates rejects
+        let mut verifier = PredicateVerifier::new(issuers.clone());
+        verifier.add_predicate(bound.clone(), &bound_pk.vk);
+        assert!(matches!(
+            verifier.verify(&presentation, NONCE),
+            Err(PredicateError::IncorrectNumberOfSnarkProofs {
+                expected: 1,
+                found: 3
+            })
+        ));
+
+        // References to messages that don't exist
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(
+            Predicate::NotEqual {
+                message_1: (0, 2),
+                message_2: (2, 0),
+            },
+            &not_equal_pk,
+        );
+        assert!(matches!(
+            prover.prove(&mut rng, NONCE),
+            Err(PredicateError::InvalidMessageRef((2, 0)))
+        ));
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(
+            Predicate::NotEqual {
+                message_1: (0, 5),
+                message_2: (1, 0),
+            },
+            &not_equal_pk,
+        );
+        assert!(matches!(
+            prover.prove(&mut rng, NONCE),
+            Err(PredicateError::InvalidMessageRef((0, 5)))
+        ));
+    }
+
+    #[test]
+    fn prover_errors() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        // Message (0, 1) is 70000 which needs 17 bits
+        let (messages, sig_params, keypair, sig) = sig_setup_with_messages(
+            &mut rng,
+            vec![Fr::from(101u64), Fr::from(70000u64), Fr::from(103u64)],
+        );
+        let mut credentials = vec![Credential {
+            signature: sig,
+            messages,
+            params: sig_params,
+            public_key: keypair.public_key,
+        }];
+
+        let bound = |message| Predicate::Bound {
+            message,
+            min: Fr::from(100u64),
+            max: Fr::from(110u64),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        let bound_pk = bound((0, 0)).generate_proving_key(&mut rng).unwrap();
+
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(bound((0, 1)), &bound_pk);
+        assert!(matches!(
+            prover.prove(&mut rng, NONCE),
+            Err(PredicateError::ValueOutOfRange {
+                message: (0, 1),
+                bits: 16
+            })
+        ));
+
+        assert!(matches!(
+            bound((0, 0)).circuit(Some(vec![Fr::from(101u64), Fr::from(102u64)])),
+            Err(PredicateError::CommitmentSizeMismatch {
+                expected: 1,
+                found: 2
+            })
+        ));
+        assert!(matches!(
+            PredicateError::from(SynthesisError::AssignmentMissing),
+            PredicateError::MissingAssignment
+        ));
+
+        // Message (0, 2) is 103
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(
+            Predicate::Bound {
+                message: (0, 2),
+                min: Fr::from(100u64),
+                max: Fr::from(103u64),
+                bits: Some(16),
+                mode: BoundMode::default(),
+            },
+            &bound_pk,
+        );
+        match prover.prove(&mut rng, NONCE) {
+            Err(PredicateError::UnsatisfiedWitness(e)) => {
+                assert_eq!(e, "value 103 is not < max 103")
+            }
+            r => panic!("unexpected {:?}", r.map(|_| ())),
+        }
+
+        // Messages other than the signed ones
+        credentials[0].messages[2] = Fr::from(104u64);
+        assert!(matches!(
+            credentials[0].verify(),
+            Err(PredicateError::BBSPlusError(_))
+        ));
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(bound((0, 0)), &bound_pk);
+        assert!(matches!(
+            prover.prove(&mut rng, NONCE),
+            Err(PredicateError::BBSPlusError(_))
+        ));
+    }
+
+    #[test]
+    fn snark_proofs_bound_to_composite_proof() {
+        // The composite proof is only valid with the LegoGroth16 proofs it was created with
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let (messages, sig_params, keypair, sig) = sig_setup(&mut rng, 5);
+        let credentials = vec![Credential {
+            signature: sig,
+            messages,
+            params: sig_params.clone(),
+            public_key: keypair.public_key.clone(),
+        }];
+
+        let bound = Predicate::Bound {
+            message: (0, 1),
+            min: Fr::from(100u64),
+            max: Fr::from(110u64),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        let not_equal = Predicate::NotEqualPublic {
+            message: (0, 3),
+            public: Fr::from(1000u64),
+        };
+        let bound_pk = bound.generate_proving_key(&mut rng).unwrap();
+        let not_equal_pk = not_equal.generate_proving_key(&mut rng).unwrap();
+
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(bound.clone(), &bound_pk);
+        prover.add_predicate(not_equal.clone(), &not_equal_pk);
+        let presentation_1 = prover.prove(&mut rng, NONCE).unwrap();
+        let presentation_2 = prover.prove(&mut rng, NONCE).unwrap();
+
+        let mut verifier = PredicateVerifier::new(vec![(sig_params, keypair.public_key)]);
+        verifier.add_predicate(bound, &bound_pk.vk);
+        verifier.add_predicate(not_equal, &not_equal_pk.vk);
+        verifier.verify(&presentation_1, NONCE).unwrap();
+        verifier.verify(&presentation_2, NONCE).unwrap();
+
+        // Swapping the LegoGroth16 proofs between presentations
+        let mut swapped_1 = presentation_1.clone();
+        let mut swapped_2 = presentation_2.clone();
+        swapped_1.snark_proofs = presentation_2.snark_proofs.clone();
+        swapped_2.snark_proofs = presentation_1.snark_proofs.clone();
+        assert!(verifier.verify(&swapped_1, NONCE).is_err());
+        assert!(verifier.verify(&swapped_2, NONCE).is_err());
+
+        // Swapping only 1 of the LegoGroth16 proofs
+        let mut swapped = presentation_1.clone();
+        swapped.snark_proofs[1] = presentation_2.snark_proofs[1].clone();
+        assert!(verifier.verify(&swapped, NONCE).is_err());
+
+        // Re-randomizing a LegoGroth16 proof keeps it valid and keeps the commitment `d` but the presentation
+        // is rejected as the composite proof is bound to the original
+        let r = Fr::rand(&mut rng);
+        let mut rerandomized = presentation_1.clone();
+        let snark_proof = &mut rerandomized.snark_proofs[0];
+        snark_proof.a = snark_proof
+            .a
+            .mul(r.inverse().unwrap().into_repr())
+            .into_affine();
+        snark_proof.b = snark_proof.b.mul(r.into_repr()).into_affine();
+        assert_eq!(snark_proof.d, presentation_1.snark_proofs[0].d);
+        verify_proof(
+            &prepare_verifying_key(&bound_pk.vk),
+            snark_proof,
+            &[Fr::from(100u64), Fr::from(110u64)],
+        )
+        .unwrap();
+        assert!(verifier.verify(&rerandomized, NONCE).is_err());
+    }
+
+    #[test]
+    fn presentation_serialization() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let (messages, sig_params, keypair, sig) = sig_setup(&mut rng, 5);
+        let credentials = vec![Credential {
+            signature: sig,
+            messages,
+            params: sig_params.clone(),
+            public_key: keypair.public_key.clone(),
+        }];
+
+        let bound = Predicate::Bound {
+            message: (0, 1),
+            min: Fr::from(100u64),
+            max: Fr::from(110u64),
+            bits: Some(16),
+            mode: BoundMode::default(),
+        };
+        let bound_pk = bound.generate_proving_key(&mut rng).unwrap();
+
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(bound.clone(), &bound_pk);
+        let presentation = prover.prove(&mut rng, NONCE).unwrap();
+
+        let mut verifier = PredicateVerifier::new(vec![(sig_params, keypair.public_key)]);
+        verifier.add_predicate(bound, &bound_pk.vk);
+
+        let mut compressed = vec![];
+        presentation.serialize(&mut compressed).unwrap();
+        assert_eq!(compressed.len(), presentation.serialized_size());
+        assert_eq!(&compressed[..4], &PRESENTATION_MAGIC);
+        assert_eq!(compressed[4], PRESENTATION_VERSION);
+        let mut uncompressed = vec![];
+        presentation
+            .serialize_uncompressed(&mut uncompressed)
+            .unwrap();
+        assert_eq!(uncompressed.len(), presentation.uncompressed_size());
+        assert!(uncompressed.len() > compressed.len());
+
+        let deserialized = Presentation::deserialize(&compressed[..]).unwrap();
+        verifier.verify(&deserialized, NONCE).unwrap();
+        let mut bytes = vec![];
+        deserialized.serialize(&mut bytes).unwrap();
+        assert_eq!(bytes, compressed);
+
+        let deserialized = Presentation::deserialize_uncompressed(&uncompressed[..]).unwrap();
+        verifier.verify(&deserialized, NONCE).unwrap();
+        let mut bytes = vec![];
+        deserialized.serialize_uncompressed(&mut bytes).unwrap();
+        assert_eq!(bytes, uncompressed);
+
+        // One encoding cannot be read as the other
+        assert!(Presentation::deserialize_uncompressed(&compressed[..]).is_err());
+        assert!(Presentation::deserialize(&uncompressed[..]).is_err());
+
+        // Truncated inputs
+        for len in [0, 3, 6, compressed.len() / 2, compressed.len() - 1] {
+            assert!(Presentation::deserialize(&compressed[..len]).is_err());
+        }
+        for len in [0, 5, uncompressed.len() / 2, uncompressed.len() - 1] {
+            assert!(Presentation::deserialize_uncompressed(&uncompressed[..len]).is_err());
+        }
+
+        // Wrong magic or version
+        let mut bytes = compressed.clone();
+        bytes[0] ^= 1;
+        assert!(Presentation::deserialize(&bytes[..]).is_err());
+        let mut bytes = compressed.clone();
+        bytes[4] = PRESENTATION_VERSION + 1;
+        assert!(Presentation::deserialize(&bytes[..]).is_err());
+
+        // Presentation claiming different public inputs
+        let mut tampered = presentation.clone();
+        tampered.public_inputs[0][1] = Fr::from(120u64);
+        assert!(matches!(
+            verifier.verify(&tampered, NONCE),
+            Err(PredicateError::PresentationMismatch)
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::bounds::{
+    check_bit_length, check_cmp, check_cmp_bits, enforce_bit_length, enforce_cmp_in_bits,
+    max_cmp_bits,
+};
+use crate::error::PredicateError;
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::cmp::Ordering;
+use ark_std::format;
+
+/// Enforce num / den <= p / q, i.e. q * num <= p * den, where `num` and `den` are hidden and `p` and `q` are
+/// public. Both `den` and `q` must be non-zero. This is useful for proving a debt-to-income ratio where debt and
+/// income might be signed under different signatures.
+#[derive(Clone)]
+pub struct RatioBoundCircuit<F: Field> {
+    pub p: Option<F>,
+    pub q: Option<F>,
+    pub num: Option<F>,
+    pub den: Option<F>,
+    /// Upper bound on the bit-size of `num`, `den`, `p` and `q`. The products are thus less than `2^(2 * bits)`
+    /// and cannot wrap around the modulus. `2 * bits` must be at most `max_cmp_bits`, so `bits` at most 126.
+    pub bits: usize,
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
+    for RatioBoundCircuit<ConstraintF>
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let product_bits = self.bits.saturating_mul(2);
+        if product_bits > max_cmp_bits::<ConstraintF>() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // `num` and `den` are the committed witnesses, in that order
+        let num = FpVar::new_variable(
+            cs.clone(),
+            || self.num.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let den = FpVar::new_variable(
+            cs.clone(),
+            || self.den.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+
+        let p = FpVar::new_variable(
+            cs.clone(),
+            || self.p.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+        let q = FpVar::new_variable(
+            cs.clone(),
+            || self.q.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+
+        for v in [&num, &den, &p, &q] {
+            enforce_bit_length(v, self.bits)?;
+        }
+
+        // A zero denominator would make the comparison meaningless
+        den.enforce_not_equal(&FpVar::zero())?;
+        q.enforce_not_equal(&FpVar::zero())?;
+
+        let lhs = &q * &num;
+        let rhs = &p * &den;
+        // q * num <= p * den
+        enforce_cmp_in_bits(&lhs, &rhs, Ordering::Less, true, product_bits)
+    }
+}
+
+impl<F: PrimeField> RatioBoundCircuit<F> {
+    /// Check natively that the values satisfy the circuit, in the same order as the circuit, to fail with a
+    /// description of the unsatisfied check rather than create a proof that fails verification
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        check_cmp_bits::<F>(self.bits.saturating_mul(2))?;
+        let num = self.num.ok_or(PredicateError::MissingAssignment)?;
+        let den = self.den.ok_or(PredicateError::MissingAssignment)?;
+        let p = self.p.ok_or(PredicateError::MissingAssignment)?;
+        let q = self.q.ok_or(PredicateError::MissingAssignment)?;
+        for (name, v) in [("num", &num), ("den", &den), ("p", &p), ("q", &q)] {
+            check_bit_length(name, v, self.bits)?;
+        }
+        for (name, v) in [("den", &den), ("q", &q)] {
+            if v.is_zero() {
+                return Err(PredicateError::UnsatisfiedWitness(format!("{} is 0", name)));
+            }
+        }
+        check_cmp(
+            ("q * num", &(q * num)),
+            ("p * den", &(p * den)),
+            Ordering::Less,
+            true,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presentation::Predicate;
+    use crate::tests::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn ratio_bound_check_messages() {
+        // Prover has 2 BBS+ signatures, one over his debts and another over his income, and he wants to prove
+        // that debt / income <= p / q for public p and q without revealing debt or income.
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        // Debt is the message at index 1 of the 1st credential, 102, and income the message at index 3 of the
+        // 2nd, 104
+        let credentials = [
+            credential(&mut rng, (101..=105u64).map(Fr::from).collect()),
+            credential(&mut rng, (101..=110u64).map(Fr::from).collect()),
+        ];
+        let ratio = |p: u64, q: u64| Predicate::RatioBound {
+            num: (0, 1),
+            den: (1, 3),
+            p: Fr::from(p),
+            q: Fr::from(q),
+            bits: 64,
+        };
+
+        // 102 / 104 <= 51 / 52 as 52 * 102 = 51 * 104
+        let (presentation, vks) =
+            prove_predicates(&mut rng, &credentials, &[ratio(51, 52)]).unwrap();
+        verify_predicates(&credentials, &[ratio(51, 52)], &vks, &presentation).unwrap();
+        // Verifier expecting another ratio rejects
+        assert!(matches!(
+            verify_predicates(&credentials, &[ratio(50, 52)], &vks, &presentation),
+            Err(PredicateError::PresentationMismatch)
+        ));
+        // 102 / 104 > 50 / 52
+        assert!(matches!(
+            prove_predicates(&mut rng, &credentials, &[ratio(50, 52)]),
+            Err(PredicateError::UnsatisfiedWitness(_))
+        ));
+
+        let is_satisfied = |num: u64, den: u64, p: u64, q: u64| {
+            let circuit = RatioBoundCircuit {
+                p: Some(Fr::from(p)),
+                q: Some(Fr::from(q)),
+                num: Some(Fr::from(num)),
+                den: Some(Fr::from(den)),
+                bits: 64,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            // Synthesis fails when a denominator is 0 as it has no inverse
+            circuit.generate_constraints(cs.clone()).is_ok() && cs.is_satisfied().unwrap()
+        };
+
+        assert!(is_satisfied(102, 104, 51, 52));
+        assert!(is_satisfied(30, 100, 1, 3));
+        assert!(!is_satisfied(102, 104, 50, 52));
+        assert!(!is_satisfied(34, 100, 1, 3));
+        // Denominators can't be 0
+        assert!(!is_satisfied(0, 0, 1, 3));
+        assert!(!is_satisfied(30, 100, 1, 0));
+
+        // A "negative" numerator does not fit in `bits`
+        let circuit = RatioBoundCircuit {
+            p: Some(Fr::from(1u64)),
+            q: Some(Fr::from(3u64)),
+            num: Some(-Fr::from(1u64)),
+            den: Some(Fr::from(100u64)),
+            bits: 64,
+        };
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn check_witness_and_bits() {
+        let check = |num: u64, den: u64, p: u64, q: u64| {
+            let circuit = RatioBoundCircuit {
+                p: Some(Fr::from(p)),
+                q: Some(Fr::from(q)),
+                num: Some(Fr::from(num)),
+                den: Some(Fr::from(den)),
+                bits: 16,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let satisfied = circuit.clone().generate_constraints(cs.clone()).is_ok()
+                && cs.is_satisfied().unwrap();
+            let result = circuit.check_witness();
+            assert_eq!(result.is_ok(), satisfied);
+            match result {
+                Ok(()) => None,
+                Err(PredicateError::UnsatisfiedWitness(e)) => Some(e),
+                Err(e) => panic!("unexpected {:?}", e),
+            }
+        };
+        assert_eq!(check(102, 104, 51, 52), None);
+        assert_eq!(
+            check(34, 100, 1, 3).as_deref(),
+            Some("q * num 102 is not <= p * den 100")
+        );
+        assert_eq!(check(30, 0, 1, 3).as_deref(), Some("den is 0"));
+        assert_eq!(
+            check(70000, 100, 1, 3).as_deref(),
+            Some("num 70000 does not fit in 16 bits")
+        );
+
+        // The products have twice as many bits as the values so those must fit in `max_cmp_bits / 2` bits
+        let max = max_cmp_bits::<Fr>();
+        for (bits, supported) in [(max / 2, true), (max / 2 + 1, false), (usize::MAX, false)] {
+            let circuit = RatioBoundCircuit {
+                p: Some(Fr::from(1u64)),
+                q: Some(Fr::from(3u64)),
+                num: Some(Fr::from(30u64)),
+                den: Some(Fr::from(100u64)),
+                bits,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let result = circuit.clone().generate_constraints(cs.clone());
+            if supported {
+                result.unwrap();
+                assert!(cs.is_satisfied().unwrap());
+                circuit.check_witness().unwrap();
+            } else {
+                assert!(matches!(result, Err(SynthesisError::Unsatisfiable)));
+                assert!(matches!(
+                    circuit.check_witness(),
+                    Err(PredicateError::UnsupportedBitSize { max: 253, .. })
+                ));
+            }
+        }
+    }
+}
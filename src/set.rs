@@ -0,0 +1,387 @@
+use crate::bounds::{
+    check_bit_length, check_cmp, check_cmp_bits, enforce_bit_length, enforce_cmp_in_bits,
+    to_decimal,
+};
+use crate::error::PredicateError;
+use crate::merkle::{MerklePath, MerklePathVar};
+use crate::poseidon::PoseidonParams;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::cmp::Ordering;
+use ark_std::format;
+
+/// Enforce that `member` is a leaf of the Merkle tree with root `root` and depth `depth`, i.e. `member` belongs
+/// to the set the tree was built from. `root` is the only public input.
+#[derive(Clone)]
+pub struct SetMembershipCircuit<F: PrimeField> {
+    pub root: Option<F>,
+    pub member: Option<F>,
+    pub path: Option<MerklePath<F>>,
+    pub depth: usize,
+    pub params: PoseidonParams<F>,
+}
+
+/// Enforce that `non_member` is not a leaf of the sorted Merkle tree with root `root` and depth `depth`, i.e.
+/// `non_member` does not belong to the set the tree was built from. The tree must be built with
+/// `MerkleTree::new_sorted` and the circuit proves that 2 adjacent leaves `lower` and `upper` of the tree satisfy
+/// `lower < non_member < upper`. `root` is the only public input.
+#[derive(Clone)]
+pub struct SetNonMembershipCircuit<F: PrimeField> {
+    pub root: Option<F>,
+    pub non_member: Option<F>,
+    pub lower: Option<F>,
+    pub lower_path: Option<MerklePath<F>>,
+    pub upper: Option<F>,
+    pub upper_path: Option<MerklePath<F>>,
+    pub depth: usize,
+    /// Bit-size of the values in the set, same as used in `MerkleTree::new_sorted`
+    pub bits: usize,
+    pub params: PoseidonParams<F>,
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
+    for SetMembershipCircuit<ConstraintF>
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        // The member is the committed witness so allocate it first
+        let member = FpVar::new_variable(
+            cs.clone(),
+            || self.member.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let path = MerklePathVar::new_witness(cs.clone(), self.path.as_ref(), self.depth)?;
+
+        let root = FpVar::new_variable(
+            cs.clone(),
+            || self.root.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+
+        path.root(&self.params, &member)?.enforce_equal(&root)
+    }
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
+    for SetNonMembershipCircuit<ConstraintF>
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        // The non-member is the committed witness so allocate it first
+        let non_member = FpVar::new_variable(
+            cs.clone(),
+            || self.non_member.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let lower = FpVar::new_variable(
+            cs.clone(),
+            || self.lower.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let upper = FpVar::new_variable(
+            cs.clone(),
+            || self.upper.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Witness,
+        )?;
+        let lower_path =
+            MerklePathVar::new_witness(cs.clone(), self.lower_path.as_ref(), self.depth)?;
+        let upper_path =
+            MerklePathVar::new_witness(cs.clone(), self.upper_path.as_ref(), self.depth)?;
+
+        let root = FpVar::new_variable(
+            cs.clone(),
+            || self.root.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+
+        // Both `lower` and `upper` are leaves of the tree
+        lower_path
+            .root(&self.params, &lower)?
+            .enforce_equal(&root)?;
+        upper_path
+            .root(&self.params, &upper)?
+            .enforce_equal(&root)?;
+
+        // and are adjacent, i.e. the index of `upper` is 1 more than the index of `lower`. As the leaves are
+        // sorted, no leaf can lie between them.
+        upper_path
+            .index()?
+            .enforce_equal(&(lower_path.index()? + ConstraintF::one()))?;
+
+        enforce_bit_length(&non_member, self.bits)?;
+        enforce_bit_length(&lower, self.bits)?;
+        enforce_bit_length(&upper, self.bits)?;
+        // lower < non_member < upper
+        enforce_cmp_in_bits(&non_member, &lower, Ordering::Greater, false, self.bits)?;
+        enforce_cmp_in_bits(&non_member, &upper, Ordering::Less, false, self.bits)
+    }
+}
+
+impl<F: PrimeField> SetMembershipCircuit<F> {
+    /// Check natively that the values satisfy the circuit. Without it, proving with a path of another leaf
+    /// creates a proof that fails verification.
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        let member = self.member.ok_or(PredicateError::MissingAssignment)?;
+        let root = self.root.ok_or(PredicateError::MissingAssignment)?;
+        let path = self
+            .path
+            .as_ref()
+            .ok_or(PredicateError::MissingAssignment)?;
+        check_path(("member", &member), path, self.depth, &self.params, &root)
+    }
+}
+
+impl<F: PrimeField> SetNonMembershipCircuit<F> {
+    /// Check natively that the values satisfy the circuit, in the same order as the circuit
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        check_cmp_bits::<F>(self.bits)?;
+        let non_member = self.non_member.ok_or(PredicateError::MissingAssignment)?;
+        let root = self.root.ok_or(PredicateError::MissingAssignment)?;
+        let lower = self.lower.ok_or(PredicateError::MissingAssignment)?;
+        let upper = self.upper.ok_or(PredicateError::MissingAssignment)?;
+        let lower_path = self
+            .lower_path
+            .as_ref()
+            .ok_or(PredicateError::MissingAssignment)?;
+        let upper_path = self
+            .upper_path
+            .as_ref()
+            .ok_or(PredicateError::MissingAssignment)?;
+        check_path(
+            ("lower", &lower),
+            lower_path,
+            self.depth,
+            &self.params,
+            &root,
+        )?;
+        check_path(
+            ("upper", &upper),
+            upper_path,
+            self.depth,
+            &self.params,
+            &root,
+        )?;
+        if upper_path.index != lower_path.index + 1 {
+            return Err(PredicateError::UnsatisfiedWitness(format!(
+                "upper at index {} is not next to lower at index {}",
+                upper_path.index, lower_path.index
+            )));
+        }
+        for (name, v) in [
+            ("non_member", &non_member),
+            ("lower", &lower),
+            ("upper", &upper),
+        ] {
+            check_bit_length(name, v, self.bits)?;
+        }
+        check_cmp(
+            ("non_member", &non_member),
+            ("lower", &lower),
+            Ordering::Greater,
+            false,
+        )?;
+        check_cmp(
+            ("non_member", &non_member),
+            ("upper", &upper),
+            Ordering::Less,
+            false,
+        )
+    }
+}
+
+/// Native counterpart of `MerklePathVar::root` followed by `enforce_equal` which names the leaf in the error
+fn check_path<F: PrimeField>(
+    (name, leaf): (&str, &F),
+    path: &MerklePath<F>,
+    depth: usize,
+    params: &PoseidonParams<F>,
+    root: &F,
+) -> Result<(), PredicateError> {
+    if path.siblings.len() != depth {
+        return Err(PredicateError::UnsatisfiedWitness(format!(
+            "path of {} has {} siblings but the tree has depth {}",
+            name,
+            path.siblings.len(),
+            depth
+        )));
+    }
+    if path.root(params, *leaf) != *root {
+        return Err(PredicateError::UnsatisfiedWitness(format!(
+            "{} {} is not the leaf at index {} of the tree",
+            name,
+            to_decimal(leaf),
+            path.index
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::MerkleTree;
+    use crate::presentation::Predicate;
+    use crate::tests::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    fn set(values: &[u64]) -> Vec<Fr> {
+        values.iter().map(|v| Fr::from(*v)).collect()
+    }
+
+    #[test]
+    fn set_membership_messages() {
+        // Prover has a BBS+ signature and he wants to prove that one of the signed messages, like his nationality,
+        // belongs to a public allow-list and does not belong to a public deny-list without revealing the message.
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        // The message at index 4 is 105
+        let credentials = [credential(&mut rng, (101..=110u64).map(Fr::from).collect())];
+        let params = PoseidonParams::<Fr>::new();
+
+        // Verifier's allow-list and deny-list, the latter not sorted
+        let allowed = set(&[36, 76, 105, 124, 250, 276, 380, 392, 484, 840]);
+        let denied = set(&[408, 104, 364, 760, 192, 106]);
+        let membership = |message, allowed: &[Fr]| Predicate::SetMembership {
+            message,
+            tree: MerkleTree::new(&params, allowed).unwrap(),
+            params: params.clone(),
+        };
+        let non_membership = |message| Predicate::SetNonMembership {
+            message,
+            tree: MerkleTree::new_sorted(&params, &denied, 16).unwrap(),
+            bits: 16,
+            params: params.clone(),
+        };
+
+        let predicates = [membership((0, 4), &allowed), non_membership((0, 4))];
+        let (presentation, vks) = prove_predicates(&mut rng, &credentials, &predicates).unwrap();
+        verify_predicates(&credentials, &predicates, &vks, &presentation).unwrap();
+
+        // Verifier with another allow-list rejects
+        let other = [membership((0, 4), &allowed[1..]), non_membership((0, 4))];
+        assert!(matches!(
+            verify_predicates(&credentials, &other, &vks, &presentation),
+            Err(PredicateError::PresentationMismatch)
+        ));
+
+        // 101 is not in the allow-list and 104 is in the deny-list
+        assert!(matches!(
+            prove_predicates(&mut rng, &credentials, &[membership((0, 0), &allowed)]),
+            Err(PredicateError::NoSetPath)
+        ));
+        assert!(matches!(
+            prove_predicates(&mut rng, &credentials, &[non_membership((0, 3))]),
+            Err(PredicateError::NoSetPath)
+        ));
+    }
+
+    #[test]
+    fn set_membership_circuit() {
+        let params = PoseidonParams::<Fr>::new();
+        let tree = MerkleTree::new(&params, &set(&[36, 76, 105, 124, 250])).unwrap();
+        let is_satisfied = |member: u64, i: usize| {
+            let circuit = SetMembershipCircuit {
+                root: Some(tree.root()),
+                member: Some(Fr::from(member)),
+                path: Some(tree.path(i)),
+                depth: tree.depth(),
+                params: params.clone(),
+            };
+            let checked = circuit.check_witness().is_ok();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            let satisfied = cs.is_satisfied().unwrap();
+            assert_eq!(checked, satisfied);
+            satisfied
+        };
+
+        assert!(is_satisfied(105, 2));
+        // Path of another leaf
+        assert!(!is_satisfied(105, 1));
+        // A value not in the set can't satisfy the circuit, whichever path is used
+        for i in 0..tree.leaves().len() {
+            assert!(!is_satisfied(101, i));
+        }
+
+        let circuit = SetMembershipCircuit {
+            root: Some(tree.root()),
+            member: Some(Fr::from(105u64)),
+            path: Some(tree.path(1)),
+            depth: tree.depth(),
+            params: params.clone(),
+        };
+        match circuit.check_witness() {
+            Err(PredicateError::UnsatisfiedWitness(msg)) => {
+                assert_eq!(msg, "member 105 is not the leaf at index 1 of the tree")
+            }
+            _ => panic!("expected an unsatisfied witness"),
+        }
+    }
+
+    #[test]
+    fn set_non_membership_circuit() {
+        let params = PoseidonParams::<Fr>::new();
+        let bits = 16;
+        let tree =
+            MerkleTree::new_sorted(&params, &set(&[408, 104, 364, 760, 192, 106]), bits).unwrap();
+        let is_satisfied = |non_member: Fr, l: usize, u: usize| {
+            let circuit = SetNonMembershipCircuit {
+                root: Some(tree.root()),
+                non_member: Some(non_member),
+                lower: Some(tree.leaves()[l]),
+                lower_path: Some(tree.path(l)),
+                upper: Some(tree.leaves()[u]),
+                upper_path: Some(tree.path(u)),
+                depth: tree.depth(),
+                bits,
+                params: params.clone(),
+            };
+            let checked = circuit.check_witness().is_ok();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            let satisfied = cs.is_satisfied().unwrap();
+            assert_eq!(checked, satisfied);
+            satisfied
+        };
+
+        // Value below the first leaf of the deny-list, above the last leaf and between 2 leaves
+        for value in [Fr::from(3u64), Fr::from(1000u64), Fr::from(105u64)] {
+            let (lower_path, upper_path) = tree.non_membership_paths(&value).unwrap();
+            assert!(is_satisfied(value, lower_path.index, upper_path.index));
+        }
+
+        // A member of the deny-list can't satisfy the circuit. Try bracketing it with leaves around it,
+        // adjacent or not.
+        let member = Fr::from(104u64);
+        let idx = tree.index_of(&member).unwrap();
+        for (l, u) in [(idx - 1, idx), (idx, idx + 1), (idx - 1, idx + 1)] {
+            assert!(!is_satisfied(member, l, u));
+        }
+
+        let circuit = SetNonMembershipCircuit {
+            root: Some(tree.root()),
+            non_member: Some(member),
+            lower: Some(tree.leaves()[idx - 1]),
+            lower_path: Some(tree.path(idx - 1)),
+            upper: Some(tree.leaves()[idx]),
+            upper_path: Some(tree.path(idx)),
+            depth: tree.depth(),
+            bits,
+            params: params.clone(),
+        };
+        match circuit.check_witness() {
+            Err(PredicateError::UnsatisfiedWitness(msg)) => {
+                assert_eq!(msg, "non_member 104 is not < upper 104")
+            }
+            _ => panic!("expected an unsatisfied witness"),
+        }
+    }
+}
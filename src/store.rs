@@ -0,0 +1,479 @@
+use crate::circuit_id::CircuitId;
+use crate::error::PredicateError;
+use crate::presentation::Predicate;
+use crate::Fr;
+use ark_bls12_381::Bls12_381;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::RngCore;
+use legogroth16::{generate_random_parameters, ProvingKey, VerifyingKey};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// NOTE: Keys are stored as files named by the hex encoded digest of the circuit ID with extension `pk` or `vk`.
+// A file starts with `KEY_FILE_MAGIC`, `KEY_FILE_VERSION` and 1 byte for the kind of key (0 for proving and 1 for
+// verifying), followed by the key serialized as a compressed `IdentifiedKey`.
+//
+// A file is written to a temporary file in the same directory, synced and renamed into place, so a crash never
+// leaves a partially written key under a key's name. The verifying key is written before the proving key, so a
+// valid proving key file always has the matching verifying key file next to it or none, in which case the
+// verifying key is restored from the proving key. A truncated or otherwise undecodable key file, eg. from a copy
+// that did not complete, fails with `InvalidKeyFile` rather than being replaced, as replacing a proving key also
+// replaces the verifying key verifiers may already have. `remove_keys` deletes the files of a circuit so its keys
+// are generated again. Keys of a circuit are generated once per store as the lookup,
+// generation and writes are done under a lock per circuit, but stores of different processes sharing a
+// directory can each generate keys for the same circuit and overwrite each other's.
+
+/// Magic bytes at the start of a key file
+pub const KEY_FILE_MAGIC: [u8; 4] = *b"BBSK";
+/// Version of the key file format
+pub const KEY_FILE_VERSION: u8 = 1;
+
+/// Stores proving and verifying keys on disk keyed by the ID of their circuit. Keys are loaded from disk
+/// the first time they are needed and kept in memory afterwards.
+pub struct ParameterStore {
+    dir: PathBuf,
+    proving_keys: Mutex<BTreeMap<CircuitId, Arc<ProvingKey<Bls12_381>>>>,
+    verifying_keys: Mutex<BTreeMap<CircuitId, Arc<VerifyingKey<Bls12_381>>>>,
+    /// Held while the keys of a circuit are looked up, generated or written
+    locks: Mutex<BTreeMap<CircuitId, Arc<Mutex<()>>>>,
+}
+
+#[derive(Clone, Copy)]
+enum KeyKind {
+    Proving = 0,
+    Verifying = 1,
+}
+
+impl ParameterStore {
+    /// Keys are stored in the existing directory `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            proving_keys: Mutex::new(BTreeMap::new()),
+            verifying_keys: Mutex::new(BTreeMap::new()),
+            locks: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Proving key of the circuit. If the store does not have it, it is generated using `rng` and saved along
+    /// with the verifying key. Fails with `InvalidKeyFile` if its file is corrupt.
+    pub fn proving_key<C: ConstraintSynthesizer<Fr> + Clone, R: RngCore>(
+        &self,
+        circuit: C,
+        commit_witness_count: usize,
+        rng: &mut R,
+    ) -> Result<Arc<ProvingKey<Bls12_381>>, PredicateError> {
+        let circuit_id = CircuitId::new(circuit.clone(), commit_witness_count)?;
+        let lock = self.lock(&circuit_id);
+        let _guard = lock.lock().unwrap();
+        if let Some(pk) = self.proving_keys.lock().unwrap().get(&circuit_id) {
+            return Ok(pk.clone());
+        }
+        let path = self.path(&circuit_id, KeyKind::Proving);
+        let pk = match read_key::<ProvingKey<Bls12_381>>(&path, KeyKind::Proving, &circuit_id) {
+            Ok(pk) => {
+                circuit_id.check_verifying_key(&pk.vk)?;
+                pk
+            }
+            Err(e) if is_missing(&e) => {
+                let pk = generate_random_parameters::<Bls12_381, _, _>(
+                    circuit,
+                    commit_witness_count,
+                    rng,
+                )?;
+                write_key(
+                    &self.path(&circuit_id, KeyKind::Verifying),
+                    KeyKind::Verifying,
+                    &circuit_id,
+                    &pk.vk,
+                )?;
+                write_key(&path, KeyKind::Proving, &circuit_id, &pk)?;
+                pk
+            }
+            Err(e) => return Err(e),
+        };
+        let pk = Arc::new(pk);
+        self.proving_keys
+            .lock()
+            .unwrap()
+            .insert(circuit_id, pk.clone());
+        Ok(pk)
+    }
+
+    /// Verifying key of the circuit. If its file is missing, it is restored from the proving key's file. Fails if
+    /// the store has neither as a verifying key must not be generated independently of the proving key, and with
+    /// `InvalidKeyFile` if the file read is corrupt.
+    pub fn verifying_key<C: ConstraintSynthesizer<Fr>>(
+        &self,
+        circuit: C,
+        commit_witness_count: usize,
+    ) -> Result<Arc<VerifyingKey<Bls12_381>>, PredicateError> {
+        let circuit_id = CircuitId::new(circuit, commit_witness_count)?;
+        let lock = self.lock(&circuit_id);
+        let _guard = lock.lock().unwrap();
+        if let Some(vk) = self.verifying_keys.lock().unwrap().get(&circuit_id) {
+            return Ok(vk.clone());
+        }
+        let path = self.path(&circuit_id, KeyKind::Verifying);
+        let vk = match read_key(&path, KeyKind::Verifying, &circuit_id) {
+            Ok(vk) => vk,
+            Err(e) if is_missing(&e) => {
+                let pk: ProvingKey<Bls12_381> = read_key(
+                    &self.path(&circuit_id, KeyKind::Proving),
+                    KeyKind::Proving,
+                    &circuit_id,
+                )
+                .map_err(|pk_error| if is_missing(&pk_error) { e } else { pk_error })?;
+                write_key(&path, KeyKind::Verifying, &circuit_id, &pk.vk)?;
+                pk.vk
+            }
+            Err(e) => return Err(e),
+        };
+        circuit_id.check_verifying_key(&vk)?;
+        let vk = Arc::new(vk);
+        self.verifying_keys
+            .lock()
+            .unwrap()
+            .insert(circuit_id, vk.clone());
+        Ok(vk)
+    }
+
+    /// Delete the key files of the circuit and forget its keys, so that `proving_key` generates new ones. For
+    /// recovering from corrupt key files, as the verifying key changes.
+    pub fn remove_keys<C: ConstraintSynthesizer<Fr>>(
+        &self,
+        circuit: C,
+        commit_witness_count: usize,
+    ) -> Result<(), PredicateError> {
+        let circuit_id = CircuitId::new(circuit, commit_witness_count)?;
+        let lock = self.lock(&circuit_id);
+        let _guard = lock.lock().unwrap();
+        self.proving_keys.lock().unwrap().remove(&circuit_id);
+        self.verifying_keys.lock().unwrap().remove(&circuit_id);
+        // The proving key first so that a failure does not leave it without its verifying key
+        for kind in [KeyKind::Proving, KeyKind::Verifying] {
+            match fs::remove_file(self.path(&circuit_id, kind)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as `proving_key` for the circuit of `predicate`
+    pub fn predicate_proving_key<R: RngCore>(
+        &self,
+        predicate: &Predicate,
+        rng: &mut R,
+    ) -> Result<Arc<ProvingKey<Bls12_381>>, PredicateError> {
+        self.proving_key(
+            predicate.circuit(None)?,
+            predicate.committed_messages().len(),
+            rng,
+        )
+    }
+
+    /// Same as `verifying_key` for the circuit of `predicate`
+    pub fn predicate_verifying_key(
+        &self,
+        predicate: &Predicate,
+    ) -> Result<Arc<VerifyingKey<Bls12_381>>, PredicateError> {
+        self.verifying_key(
+            predicate.circuit(None)?,
+            predicate.committed_messages().len(),
+        )
+    }
+
+    /// Same as `remove_keys` for the circuit of `predicate`
+    pub fn predicate_remove_keys(&self, predicate: &Predicate) -> Result<(), PredicateError> {
+        self.remove_keys(
+            predicate.circuit(None)?,
+            predicate.committed_messages().len(),
+        )
+    }
+
+    fn lock(&self, circuit_id: &CircuitId) -> Arc<Mutex<()>> {
+        self.locks
+            .lock()
+            .unwrap()
+            .entry(*circuit_id)
+            .or_default()
+            .clone()
+    }
+
+    fn path(&self, circuit_id: &CircuitId, kind: KeyKind) -> PathBuf {
+        let extension = match kind {
+            KeyKind::Proving => "pk",
+            KeyKind::Verifying => "vk",
+        };
+        self.dir.join(circuit_id.to_hex()).with_extension(extension)
+    }
+}
+
+/// Write the key to a temporary file next to `path` and rename it to `path` once synced
+fn write_key<K: CanonicalSerialize>(
+    path: &Path,
+    kind: KeyKind,
+    circuit_id: &CircuitId,
+    key: &K,
+) -> Result<(), PredicateError> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = PathBuf::from(tmp_path);
+    let result = write_key_file(&tmp_path, kind, circuit_id, key)
+        .and_then(|_| fs::rename(&tmp_path, path).map_err(PredicateError::from));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn write_key_file<K: CanonicalSerialize>(
+    path: &Path,
+    kind: KeyKind,
+    circuit_id: &CircuitId,
+    key: &K,
+) -> Result<(), PredicateError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&KEY_FILE_MAGIC)?;
+    writer.write_all(&[KEY_FILE_VERSION, kind as u8])?;
+    circuit_id.serialize(&mut writer)?;
+    key.serialize(&mut writer)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    Ok(())
+}
+
+/// Read a key written by `write_key`, refusing it if it is not a key of the given kind for the given circuit.
+/// Fails with `InvalidKeyFile` if the file is truncated or cannot be decoded.
+fn read_key<K: CanonicalDeserialize>(
+    path: &Path,
+    kind: KeyKind,
+    circuit_id: &CircuitId,
+) -> Result<K, PredicateError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = [0u8; 6];
+    match reader.read_exact(&mut header) {
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            return Err(PredicateError::InvalidKeyFile)
+        }
+        r => r?,
+    }
+    if header[..4] != KEY_FILE_MAGIC || header[4] != KEY_FILE_VERSION || header[5] != kind as u8 {
+        return Err(PredicateError::InvalidKeyFile);
+    }
+    let found = CircuitId::deserialize(&mut reader).map_err(|_| PredicateError::InvalidKeyFile)?;
+    if found != *circuit_id {
+        return Err(PredicateError::CircuitIdMismatch);
+    }
+    K::deserialize(&mut reader).map_err(|_| PredicateError::InvalidKeyFile)
+}
+
+/// Whether reading a key failed as its file does not exist
+fn is_missing(e: &PredicateError) -> bool {
+    matches!(e, PredicateError::Io(e) if e.kind() == ErrorKind::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::BoundMode;
+    use crate::presentation::{Credential, PredicateProver, PredicateVerifier};
+    use crate::tests::*;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use std::fs;
+
+    fn bound(min: u64, max: u64, bits: usize) -> Predicate {
+        Predicate::Bound {
+            message: (0, 1),
+            min: Fr::from(min),
+            max: Fr::from(max),
+            bits: Some(bits),
+            mode: BoundMode::default(),
+        }
+    }
+
+    /// Empty directory for the test `name`, not shared with other tests or concurrent runs
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "bbs-predicate-store-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn store_keys_by_circuit_id() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let dir = test_dir("keys-by-circuit-id");
+
+        // Circuit ID depends on the configuration but not on the public values
+        assert_eq!(
+            bound(100, 110, 16).circuit_id().unwrap(),
+            bound(50, 60, 16).circuit_id().unwrap()
+        );
+        assert_ne!(
+            bound(100, 110, 16).circuit_id().unwrap(),
+            bound(100, 110, 32).circuit_id().unwrap()
+        );
+
+        let predicate = bound(100, 110, 16);
+        let store = ParameterStore::new(&dir);
+        // No verifying key until the proving key is generated
+        assert!(store.predicate_verifying_key(&predicate).is_err());
+        let pk = store.predicate_proving_key(&predicate, &mut rng).unwrap();
+        let circuit_id = predicate.circuit_id().unwrap();
+        assert!(dir.join(format!("{}.pk", circuit_id.to_hex())).exists());
+        assert!(dir.join(format!("{}.vk", circuit_id.to_hex())).exists());
+        // Same key for the same circuit from memory
+        assert!(Arc::ptr_eq(
+            &pk,
+            &store
+                .predicate_proving_key(&bound(50, 60, 16), &mut rng)
+                .unwrap()
+        ));
+
+        // A new store, like after a restart, loads the keys from disk rather than generating new ones
+        let store = ParameterStore::new(&dir);
+        let loaded_pk = store.predicate_proving_key(&predicate, &mut rng).unwrap();
+        let vk = store.predicate_verifying_key(&predicate).unwrap();
+        let mut bytes_1 = vec![];
+        let mut bytes_2 = vec![];
+        pk.serialize(&mut bytes_1).unwrap();
+        loaded_pk.serialize(&mut bytes_2).unwrap();
+        assert_eq!(bytes_1, bytes_2);
+
+        let (messages, sig_params, keypair, sig) = sig_setup(&mut rng, 5);
+        let credentials = vec![Credential {
+            signature: sig,
+            messages,
+            params: sig_params.clone(),
+            public_key: keypair.public_key.clone(),
+        }];
+        let mut prover = PredicateProver::new(&credentials);
+        prover.add_predicate(predicate.clone(), &loaded_pk);
+        let presentation = prover.prove(&mut rng, NONCE).unwrap();
+        let mut verifier = PredicateVerifier::new(vec![(sig_params, keypair.public_key)]);
+        verifier.add_predicate(predicate.clone(), &vk);
+        verifier.verify(&presentation, NONCE).unwrap();
+
+        // Keys of another circuit put in place of this circuit's are refused
+        let other = bound(100, 110, 32);
+        store.predicate_proving_key(&other, &mut rng).unwrap();
+        let other_circuit_id = other.circuit_id().unwrap();
+        for ext in ["pk", "vk"] {
+            fs::copy(
+                dir.join(format!("{}.{}", other_circuit_id.to_hex(), ext)),
+                dir.join(format!("{}.{}", circuit_id.to_hex(), ext)),
+            )
+            .unwrap();
+        }
+        let store = ParameterStore::new(&dir);
+        assert!(matches!(
+            store.predicate_proving_key(&predicate, &mut rng),
+            Err(PredicateError::CircuitIdMismatch)
+        ));
+        assert!(matches!(
+            store.predicate_verifying_key(&predicate),
+            Err(PredicateError::CircuitIdMismatch)
+        ));
+
+        // A proving key file is not a verifying key file
+        fs::copy(
+            dir.join(format!("{}.pk", other_circuit_id.to_hex())),
+            dir.join(format!("{}.vk", other_circuit_id.to_hex())),
+        )
+        .unwrap();
+        assert!(matches!(
+            store.predicate_verifying_key(&other),
+            Err(PredicateError::InvalidKeyFile)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn store_refuses_corrupt_key_files() {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let dir = test_dir("corrupt-key-files");
+        let predicate = bound(100, 110, 16);
+        let circuit_id = predicate.circuit_id().unwrap();
+        let pk_path = dir.join(format!("{}.pk", circuit_id.to_hex()));
+        let vk_path = dir.join(format!("{}.vk", circuit_id.to_hex()));
+
+        // Several threads asking for a key of the same circuit get the one generated by the first of them
+        let store = ParameterStore::new(&dir);
+        let pks = std::thread::scope(|s| {
+            let handles = (0..4u64)
+                .map(|i| {
+                    let store = &store;
+                    let predicate = &predicate;
+                    s.spawn(move || {
+                        let mut rng = StdRng::seed_from_u64(i + 1);
+                        store.predicate_proving_key(predicate, &mut rng).unwrap()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert!(pks.iter().all(|pk| Arc::ptr_eq(pk, &pks[0])));
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        // A truncated proving key file, like from an interrupted copy, is refused and the verifying key is kept
+        let pk_bytes = fs::read(&pk_path).unwrap();
+        let vk_bytes = fs::read(&vk_path).unwrap();
+        fs::write(&pk_path, &pk_bytes[..pk_bytes.len() / 2]).unwrap();
+        let store = ParameterStore::new(&dir);
+        assert!(matches!(
+            store.predicate_proving_key(&predicate, &mut rng),
+            Err(PredicateError::InvalidKeyFile)
+        ));
+        assert_eq!(fs::read(&vk_path).unwrap(), vk_bytes);
+        // and so is a file too short for the header
+        fs::write(&pk_path, b"BBS").unwrap();
+        assert!(matches!(
+            store.predicate_proving_key(&predicate, &mut rng),
+            Err(PredicateError::InvalidKeyFile)
+        ));
+
+        // A truncated verifying key file is refused while a missing one is restored from the proving key
+        fs::write(&pk_path, &pk_bytes).unwrap();
+        fs::write(&vk_path, &vk_bytes[..vk_bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            store.predicate_verifying_key(&predicate),
+            Err(PredicateError::InvalidKeyFile)
+        ));
+        fs::remove_file(&vk_path).unwrap();
+        store.predicate_verifying_key(&predicate).unwrap();
+        assert_eq!(fs::read(&vk_path).unwrap(), vk_bytes);
+
+        // Removing the keys has new ones generated
+        fs::write(&pk_path, &pk_bytes[..pk_bytes.len() / 2]).unwrap();
+        store.predicate_remove_keys(&predicate).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        let pk = store.predicate_proving_key(&predicate, &mut rng).unwrap();
+        let mut bytes_1 = vec![];
+        let mut bytes_2 = vec![];
+        pks[0].serialize(&mut bytes_1).unwrap();
+        pk.serialize(&mut bytes_2).unwrap();
+        assert_ne!(bytes_1, bytes_2);
+        let mut bytes = vec![];
+        pk.vk.serialize(&mut bytes).unwrap();
+        assert!(fs::read(&vk_path).unwrap().ends_with(&bytes));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,23 +1,123 @@
-use ark_ff::{Field, PrimeField};
+use crate::bounds::{
+    check_bit_length, check_cmp, check_cmp_bits, enforce_bit_length, enforce_cmp_in_bits,
+    max_cmp_bits,
+};
+use crate::error::PredicateError;
+use ark_ff::{BigInteger, Field, PrimeField};
 use ark_r1cs_std::alloc::{AllocVar, AllocationMode};
 use ark_r1cs_std::eq::EqGadget;
 use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::ns;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use std::cmp::Ordering;
+use ark_std::cmp::Ordering;
+use ark_std::{format, vec, vec::Vec};
 
-/// Enforce min < sum of values < max
+// NOTE: Each summand is constrained to be less than `2^bits` so that a sum of `n` summands is less than
+// `2^(bits + ceil(log2(n)))` and cannot wrap around the modulus. Without this, a summand close to the modulus,
+// i.e. a "negative" number, could make the sum small enough to satisfy the comparison. The bit-size of the sum
+// must itself be at most `max_cmp_bits` for the comparison to be sound, otherwise synthesis fails.
+
+/// Enforce min <= sum of values <= max
 #[derive(Clone)]
 pub struct SumBoundCheckCircuit<F: Field> {
-    min: Option<F>,
-    max: Option<F>,
-    values: Option<[F; 4]>,
+    pub min: Option<F>,
+    pub max: Option<F>,
+    /// Number of values being summed. Needed during setup when `values` is `None`.
+    pub count: usize,
+    pub values: Option<Vec<F>>,
+    /// Upper bound on the bit-size of each value
+    pub bits: usize,
 }
 
-/// Enforce sum of `smalls` < sum of `larges`
+/// Enforce sum of `smalls` < sum of `larges`. `smalls` and `larges` can be of different sizes.
 #[derive(Clone)]
 pub struct SumCompareCircuit<F: Field> {
-    smalls: Option<[F; 4]>,
-    larges: Option<[F; 4]>,
+    /// Number of values in `smalls`. Needed during setup when `smalls` is `None`.
+    pub smalls_count: usize,
+    /// Number of values in `larges`. Needed during setup when `larges` is `None`.
+    pub larges_count: usize,
+    pub smalls: Option<Vec<F>>,
+    pub larges: Option<Vec<F>>,
+    /// Upper bound on the bit-size of each value in `smalls` and `larges`
+    pub bits: usize,
+}
+
+/// Enforce min <= sum of `coefficients[i] * values[i]` <= max where the coefficients are non-negative integers
+/// less than `2^coefficient_bits`. A negative weight `-w` can't be used as `p - w` as it doesn't fit in
+/// `coefficient_bits`. The coefficients are public inputs, following `min` and `max`, so that the verifier can
+/// audit the formula.
+#[derive(Clone)]
+pub struct LinearCombinationBoundCircuit<F: Field> {
+    pub min: Option<F>,
+    pub max: Option<F>,
+    /// Number of values, and thus coefficients. Needed during setup when `values` is `None`.
+    pub count: usize,
+    pub coefficients: Option<Vec<F>>,
+    pub values: Option<Vec<F>>,
+    /// Upper bound on the bit-size of each value
+    pub bits: usize,
+    /// Upper bound on the bit-size of each coefficient
+    pub coefficient_bits: usize,
+}
+
+/// Values to be allocated as witnesses, `None` during setup. Fails if there are not `count` values.
+fn values_to_assign<F: Field>(
+    values: Option<Vec<F>>,
+    count: usize,
+) -> Result<Vec<Option<F>>, SynthesisError> {
+    match values {
+        Some(vals) if vals.len() != count => Err(SynthesisError::Unsatisfiable),
+        Some(vals) => Ok(vals.into_iter().map(Some).collect()),
+        None => Ok(vec![None; count]),
+    }
+}
+
+/// Values to be checked by `check_witness`
+fn values_to_check<F: Field>(
+    values: &Option<Vec<F>>,
+    count: usize,
+) -> Result<&[F], PredicateError> {
+    let values = values.as_ref().ok_or(PredicateError::MissingAssignment)?;
+    if values.len() != count {
+        return Err(PredicateError::CommitmentSizeMismatch {
+            expected: count,
+            found: values.len(),
+        });
+    }
+    Ok(values)
+}
+
+/// Check that each of `values` fits in `bits` bits, naming them `name[i]` in the error
+fn check_bit_lengths<F: PrimeField>(
+    name: &str,
+    values: &[F],
+    bits: usize,
+) -> Result<(), PredicateError> {
+    for (i, v) in values.iter().enumerate() {
+        check_bit_length(&format!("{}[{}]", name, i), v, bits)?;
+    }
+    Ok(())
+}
+
+fn sum<F: Field>(values: &[F]) -> F {
+    values.iter().fold(F::zero(), |acc, v| acc + v)
+}
+
+/// Upper bound on the bit-size of the sum of `count` values each of which is less than `2^bits`
+pub fn sum_bit_length(bits: usize, count: usize) -> usize {
+    if count <= 1 {
+        bits
+    } else {
+        bits.saturating_add((usize::BITS - (count - 1).leading_zeros()) as usize)
+    }
+}
+
+/// Fail if sums of `sum_bits` bits can't be compared, before allocating anything
+fn check_sum_bits<F: PrimeField>(sum_bits: usize) -> Result<(), SynthesisError> {
+    if sum_bits > max_cmp_bits::<F>() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok(())
 }
 
 impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
@@ -27,12 +127,12 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
         self,
         cs: ConstraintSystemRef<ConstraintF>,
     ) -> Result<(), SynthesisError> {
+        let sum_bits = sum_bit_length(self.bits, self.count);
+        check_sum_bits::<ConstraintF>(sum_bits)?;
+
         let mut sum_vars = vec![];
         let mut sum = ConstraintF::zero();
-        let values = match self.values {
-            Some(vals) => vals.map(|m| Some(m)),
-            _ => [None, None, None, None],
-        };
+        let values = values_to_assign(self.values, self.count)?;
 
         for v in values {
             let v = FpVar::new_variable(
@@ -47,8 +147,22 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
             sum_vars.push(v);
         }
 
-        let sum = FpVar::new_variable(cs.clone(), || Ok(sum), AllocationMode::Witness)?;
-        sum.enforce_equal(&sum_vars.iter().sum())?;
+        // Range check values only after all values are allocated since the values are the committed witnesses
+        // and thus must be allocated before any other witness
+        {
+            let _ns = ns!(cs, "range checks");
+            for v in &sum_vars {
+                enforce_bit_length(v, self.bits)?;
+            }
+        }
+
+        let sum = {
+            let _ns = ns!(cs, "summation");
+            let sum = FpVar::new_variable(cs.clone(), || Ok(sum), AllocationMode::Witness)?;
+            sum.enforce_equal(&sum_vars.iter().sum())?;
+            enforce_bit_length(&sum, sum_bits)?;
+            sum
+        };
 
         let min = FpVar::new_variable(
             cs.clone(),
@@ -61,10 +175,23 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
             AllocationMode::Input,
         )?;
 
-        // sum less than or equal to max, i.e. sum <= max
-        sum.enforce_cmp(&max, Ordering::Less, true)?;
-        // sum greater than or equal to min, i.e. sum >= min
-        sum.enforce_cmp(&min, Ordering::Greater, true)?;
+        {
+            let _ns = ns!(cs, "bound range checks");
+            enforce_bit_length(&min, sum_bits)?;
+            enforce_bit_length(&max, sum_bits)?;
+        }
+
+        let _ns = ns!(cs, "comparison");
+        {
+            let _ns = ns!(cs, "sum <= max");
+            // sum less than or equal to max, i.e. sum <= max
+            enforce_cmp_in_bits(&sum, &max, Ordering::Less, true, sum_bits)?;
+        }
+        {
+            let _ns = ns!(cs, "sum >= min");
+            // sum greater than or equal to min, i.e. sum >= min
+            enforce_cmp_in_bits(&sum, &min, Ordering::Greater, true, sum_bits)?;
+        }
         Ok(())
     }
 }
@@ -76,24 +203,21 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
         self,
         cs: ConstraintSystemRef<ConstraintF>,
     ) -> Result<(), SynthesisError> {
+        let small_sum_bits = sum_bit_length(self.bits, self.smalls_count);
+        let large_sum_bits = sum_bit_length(self.bits, self.larges_count);
+        check_sum_bits::<ConstraintF>(small_sum_bits.max(large_sum_bits))?;
+
         let mut small_sum_vars = vec![];
         let mut small_sum = ConstraintF::zero();
         let mut large_sum_vars = vec![];
         let mut large_sum = ConstraintF::zero();
 
-        let smalls = match self.smalls {
-            Some(vals) => vals.map(|m| Some(m)),
-            _ => [None, None, None, None],
-        };
-
-        let larges = match self.larges {
-            Some(vals) => vals.map(|m| Some(m)),
-            _ => [None, None, None, None],
-        };
+        let smalls = values_to_assign(self.smalls, self.smalls_count)?;
+        let larges = values_to_assign(self.larges, self.larges_count)?;
 
         // Note: Its important to allocate witness variables that are committed so allocate variables for
-        // all in `smalls` and then all in `larges` and then variables for sum and the Schnorr proof
-        // for the commitment assumes that.
+        // all in `smalls` and then all in `larges` and then variables for sum and range checks and the Schnorr
+        // proof for the commitment assumes that.
 
         for v in smalls {
             let v = FpVar::new_variable(
@@ -121,23 +245,203 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
             large_sum_vars.push(v);
         }
 
-        let small_sum = FpVar::new_variable(cs.clone(), || Ok(small_sum), AllocationMode::Witness)?;
-        small_sum.enforce_equal(&small_sum_vars.iter().sum())?;
+        {
+            let _ns = ns!(cs, "range checks");
+            for v in small_sum_vars.iter().chain(large_sum_vars.iter()) {
+                enforce_bit_length(v, self.bits)?;
+            }
+        }
 
-        let large_sum = FpVar::new_variable(cs.clone(), || Ok(large_sum), AllocationMode::Witness)?;
-        large_sum.enforce_equal(&large_sum_vars.iter().sum())?;
+        let (small_sum, large_sum) = {
+            let _ns = ns!(cs, "summation");
+            let small_sum = {
+                let _ns = ns!(cs, "smalls");
+                let small_sum =
+                    FpVar::new_variable(cs.clone(), || Ok(small_sum), AllocationMode::Witness)?;
+                small_sum.enforce_equal(&small_sum_vars.iter().sum())?;
+                enforce_bit_length(&small_sum, small_sum_bits)?;
+                small_sum
+            };
+            let large_sum = {
+                let _ns = ns!(cs, "larges");
+                let large_sum =
+                    FpVar::new_variable(cs.clone(), || Ok(large_sum), AllocationMode::Witness)?;
+                large_sum.enforce_equal(&large_sum_vars.iter().sum())?;
+                enforce_bit_length(&large_sum, large_sum_bits)?;
+                large_sum
+            };
+            (small_sum, large_sum)
+        };
 
+        let _ns = ns!(cs, "comparison");
         // small_sum less than large_sum, i.e. small_sum < large_sum
-        small_sum.enforce_cmp(&large_sum, Ordering::Less, false)?;
+        enforce_cmp_in_bits(
+            &small_sum,
+            &large_sum,
+            Ordering::Less,
+            false,
+            small_sum_bits.max(large_sum_bits),
+        )?;
         Ok(())
     }
 }
 
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
+    for LinearCombinationBoundCircuit<ConstraintF>
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        // Each term is less than `2^(bits + coefficient_bits)` as both its factors are range checked so the
+        // sum cannot wrap around the modulus
+        let sum_bits = sum_bit_length(self.bits.saturating_add(self.coefficient_bits), self.count);
+        check_sum_bits::<ConstraintF>(sum_bits)?;
+
+        let values = values_to_assign(self.values, self.count)?;
+        let coefficients = values_to_assign(self.coefficients, self.count)?;
+
+        // The values are committed so allocate them before any other witness
+        let mut value_vars = vec![];
+        for v in values {
+            value_vars.push(FpVar::new_variable(
+                cs.clone(),
+                || v.ok_or(SynthesisError::AssignmentMissing),
+                AllocationMode::Witness,
+            )?);
+        }
+        {
+            let _ns = ns!(cs, "range checks");
+            for v in &value_vars {
+                enforce_bit_length(v, self.bits)?;
+            }
+        }
+
+        let min = FpVar::new_variable(
+            cs.clone(),
+            || self.min.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+        let max = FpVar::new_variable(
+            cs.clone(),
+            || self.max.ok_or(SynthesisError::AssignmentMissing),
+            AllocationMode::Input,
+        )?;
+
+        let mut terms = vec![];
+        for (c, v) in coefficients.into_iter().zip(value_vars.iter()) {
+            let c = FpVar::new_variable(
+                cs.clone(),
+                || c.ok_or(SynthesisError::AssignmentMissing),
+                AllocationMode::Input,
+            )?;
+            {
+                let _ns = ns!(cs, "coefficient range checks");
+                // A coefficient close to the modulus would act as a negative coefficient
+                enforce_bit_length(&c, self.coefficient_bits)?;
+            }
+            let _ns = ns!(cs, "summation");
+            terms.push(c * v);
+        }
+
+        let sum: FpVar<ConstraintF> = terms.iter().sum();
+
+        {
+            let _ns = ns!(cs, "bound range checks");
+            enforce_bit_length(&min, sum_bits)?;
+            enforce_bit_length(&max, sum_bits)?;
+        }
+
+        let _ns = ns!(cs, "comparison");
+        {
+            let _ns = ns!(cs, "sum <= max");
+            // sum less than or equal to max, i.e. sum <= max
+            enforce_cmp_in_bits(&sum, &max, Ordering::Less, true, sum_bits)?;
+        }
+        {
+            let _ns = ns!(cs, "sum >= min");
+            // sum greater than or equal to min, i.e. sum >= min
+            enforce_cmp_in_bits(&sum, &min, Ordering::Greater, true, sum_bits)?;
+        }
+        Ok(())
+    }
+}
+
+// NOTE: `check_witness` of each circuit evaluates the circuit's checks natively in the same order as the circuit
+// so that a prover can fail with a description of the unsatisfied check rather than create a proof that fails
+// verification.
+
+impl<F: PrimeField> SumBoundCheckCircuit<F> {
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        let sum_bits = sum_bit_length(self.bits, self.count);
+        check_cmp_bits::<F>(sum_bits)?;
+        let values = values_to_check(&self.values, self.count)?;
+        check_bit_lengths("values", values, self.bits)?;
+        let min = self.min.ok_or(PredicateError::MissingAssignment)?;
+        let max = self.max.ok_or(PredicateError::MissingAssignment)?;
+        check_bit_length("min", &min, sum_bits)?;
+        check_bit_length("max", &max, sum_bits)?;
+        let sum = sum(values);
+        check_cmp(("sum", &sum), ("max", &max), Ordering::Less, true)?;
+        check_cmp(("sum", &sum), ("min", &min), Ordering::Greater, true)
+    }
+}
+
+impl<F: PrimeField> SumCompareCircuit<F> {
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        check_cmp_bits::<F>(
+            sum_bit_length(self.bits, self.smalls_count)
+                .max(sum_bit_length(self.bits, self.larges_count)),
+        )?;
+        let smalls = values_to_check(&self.smalls, self.smalls_count)?;
+        let larges = values_to_check(&self.larges, self.larges_count)?;
+        check_bit_lengths("smalls", smalls, self.bits)?;
+        check_bit_lengths("larges", larges, self.bits)?;
+        check_cmp(
+            ("sum of smalls", &sum(smalls)),
+            ("sum of larges", &sum(larges)),
+            Ordering::Less,
+            false,
+        )
+    }
+}
+
+impl<F: PrimeField> LinearCombinationBoundCircuit<F> {
+    pub fn check_witness(&self) -> Result<(), PredicateError> {
+        let sum_bits = sum_bit_length(self.bits.saturating_add(self.coefficient_bits), self.count);
+        check_cmp_bits::<F>(sum_bits)?;
+        let values = values_to_check(&self.values, self.count)?;
+        let coefficients = values_to_check(&self.coefficients, self.count)?;
+        check_bit_lengths("values", values, self.bits)?;
+        // Most likely a negative weight encoded as `p - w`
+        for (index, c) in coefficients.iter().enumerate() {
+            if c.into_repr().num_bits() as usize > self.coefficient_bits {
+                return Err(PredicateError::CoefficientOutOfRange {
+                    index,
+                    bits: self.coefficient_bits,
+                });
+            }
+        }
+        let min = self.min.ok_or(PredicateError::MissingAssignment)?;
+        let max = self.max.ok_or(PredicateError::MissingAssignment)?;
+        check_bit_length("min", &min, sum_bits)?;
+        check_bit_length("max", &max, sum_bits)?;
+        let sum = coefficients
+            .iter()
+            .zip(values.iter())
+            .fold(F::zero(), |acc, (c, v)| acc + *c * v);
+        check_cmp(("sum", &sum), ("max", &max), Ordering::Less, true)?;
+        check_cmp(("sum", &sum), ("min", &min), Ordering::Greater, true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::presentation::Predicate;
     use crate::tests::*;
     use ark_bls12_381::Bls12_381;
+    use ark_relations::r1cs::ConstraintSystem;
     use ark_std::{
         collections::{BTreeMap, BTreeSet},
         rand::{rngs::StdRng, SeedableRng},
@@ -153,36 +457,35 @@ mod tests {
     };
     use std::time::Instant;
 
-    #[test]
-    fn compare_messages_sum() {
-        // Prover has 2 BBS+ signatures and he wants to prove that sum of certain signed messages from
-        // the 1st signature are less than the sum of certain signed messages from 2nd signature.
-        // This can be useful in proving sum of liabilities < sum of assets where liabilities and assets
-        // are signed under different signatures.
-
+    /// Prove that the sum of messages at indices `s_m_ids` of the 1st signature is less than the sum of
+    /// messages at indices `l_m_ids` of the 2nd signature
+    fn check_compare_messages_sum(s_m_ids: &[usize], l_m_ids: &[usize]) {
         let mut rng = StdRng::seed_from_u64(0u64);
 
         // 1st BBS+ signature
-        let message_count_1 = 5;
+        let message_count_1 = 12;
         let (messages_1, sig_params_1, keypair_1, sig_1) = sig_setup(&mut rng, message_count_1);
         sig_1
             .verify(&messages_1, &keypair_1.public_key, &sig_params_1)
             .unwrap();
 
         // 2nd BBS+ signature
-        let message_count_2 = 10;
+        let message_count_2 = 15;
         let (messages_2, sig_params_2, keypair_2, sig_2) = sig_setup(&mut rng, message_count_2);
         sig_2
             .verify(&messages_2, &keypair_2.public_key, &sig_params_2)
             .unwrap();
 
-        // 8 witnesses (messages), 4 from each signature
-        let commit_witness_count = 8;
+        // All messages from both signatures are committed, those from 1st signature followed by those from 2nd
+        let commit_witness_count = s_m_ids.len() + l_m_ids.len();
         let public_inputs_count = 0;
 
         let circuit = SumCompareCircuit::<Fr> {
+            smalls_count: s_m_ids.len(),
+            larges_count: l_m_ids.len(),
             smalls: None,
             larges: None,
+            bits: 64,
         };
         let params =
             generate_random_parameters::<Bls12_381, _, _>(circuit, commit_witness_count, &mut rng)
@@ -194,36 +497,17 @@ mod tests {
         let v = Fr::rand(&mut rng);
 
         // Messages from 1st signature
-        // Indexes of the messages
-        let s_m_ids: [usize; 4] = [0, 1, 2, 3];
-        let mut smalls = s_m_ids
-            .iter()
-            .map(|i| messages_1[*i].clone())
-            .collect::<Vec<_>>();
-        let smalls: [Fr; 4] = [
-            smalls.remove(0),
-            smalls.remove(0),
-            smalls.remove(0),
-            smalls.remove(0),
-        ];
+        let smalls = s_m_ids.iter().map(|i| messages_1[*i]).collect::<Vec<_>>();
 
         // Messages from 2nd signature
-        // Indexes of the messages
-        let l_m_ids: [usize; 4] = [4, 6, 7, 8];
-        let mut larges = l_m_ids
-            .iter()
-            .map(|i| messages_2[*i].clone())
-            .collect::<Vec<_>>();
-        let larges: [Fr; 4] = [
-            larges.remove(0),
-            larges.remove(0),
-            larges.remove(0),
-            larges.remove(0),
-        ];
+        let larges = l_m_ids.iter().map(|i| messages_2[*i]).collect::<Vec<_>>();
 
         let circuit = SumCompareCircuit {
+            smalls_count: s_m_ids.len(),
+            larges_count: l_m_ids.len(),
             smalls: Some(smalls.clone()),
             larges: Some(larges.clone()),
+            bits: 64,
         };
 
         // Prover creates Groth16 proof
@@ -269,7 +553,7 @@ mod tests {
         }));
         statements.add(Statement::PedersenCommitment(PedersenCommitmentStmt {
             bases: bases.clone(),
-            commitment: commitment_to_witnesses.clone(),
+            commitment: commitment_to_witnesses,
         }));
 
         let mut meta_statements = MetaStatements::new();
@@ -302,21 +586,11 @@ mod tests {
         let mut witnesses = Witnesses::new();
         witnesses.add(PoKSignatureBBSG1Wit::new_as_witness(
             sig_1.clone(),
-            messages_1
-                .clone()
-                .into_iter()
-                .enumerate()
-                .map(|t| t)
-                .collect(),
+            messages_1.clone().into_iter().enumerate().collect(),
         ));
         witnesses.add(PoKSignatureBBSG1Wit::new_as_witness(
             sig_2.clone(),
-            messages_2
-                .clone()
-                .into_iter()
-                .enumerate()
-                .map(|t| t)
-                .collect(),
+            messages_2.clone().into_iter().enumerate().collect(),
         ));
         witnesses.add(Witness::PedersenCommitment(committed));
 
@@ -337,4 +611,533 @@ mod tests {
         println!("Time taken to verify composite proof {:?}", t2);
         println!("Total time taken to verify proof {:?}", t1 + t2);
     }
+
+    /// Prove that `min <= sum of messages at indices m_ids <= max` where all messages are from the same signature
+    fn check_sum_bound_messages(m_ids: &[usize], min: Fr, max: Fr) {
+        let mut rng = StdRng::seed_from_u64(0u64);
+
+        let message_count = 15;
+        let (messages, sig_params, keypair, sig) = sig_setup(&mut rng, message_count);
+        sig.verify(&messages, &keypair.public_key, &sig_params)
+            .unwrap();
+
+        let commit_witness_count = m_ids.len();
+        // min and max
+        let public_inputs_count = 2;
+
+        let circuit = SumBoundCheckCircuit::<Fr> {
+            min: None,
+            max: None,
+            count: m_ids.len(),
+            values: None,
+            bits: 64,
+        };
+        let params =
+            generate_random_parameters::<Bls12_381, _, _>(circuit, commit_witness_count, &mut rng)
+                .unwrap();
+
+        let pvk = prepare_verifying_key(&params.vk);
+
+        // Create commitment randomness
+        let v = Fr::rand(&mut rng);
+
+        let values = m_ids.iter().map(|i| messages[*i]).collect::<Vec<_>>();
+
+        let circuit = SumBoundCheckCircuit {
+            min: Some(min),
+            max: Some(max),
+            count: m_ids.len(),
+            values: Some(values.clone()),
+            bits: 64,
+        };
+
+        let snark_proof = create_random_proof(circuit, v, &params, &mut rng).unwrap();
+
+        verify_witness_commitment(&params.vk, &snark_proof, public_inputs_count, &values, &v)
+            .unwrap();
+
+        let mut bases = params.vk.gamma_abc_g1
+            [1 + public_inputs_count..1 + public_inputs_count + commit_witness_count]
+            .to_vec();
+        bases.push(params.vk.eta_gamma_inv_g1);
+        let mut committed = values.clone();
+        committed.push(v);
+
+        let mut statements = Statements::new();
+        statements.add(Statement::PoKBBSSignatureG1(PoKSignatureBBSG1Stmt {
+            params: sig_params.clone(),
+            public_key: keypair.public_key.clone(),
+            revealed_messages: BTreeMap::new(),
+        }));
+        statements.add(Statement::PedersenCommitment(PedersenCommitmentStmt {
+            bases: bases.clone(),
+            commitment: snark_proof.d,
+        }));
+
+        let mut meta_statements = MetaStatements::new();
+        for (i, m_i) in m_ids.iter().enumerate() {
+            meta_statements.add(MetaStatement::WitnessEquality(EqualWitnesses(
+                vec![(0, *m_i), (1, i)]
+                    .into_iter()
+                    .collect::<BTreeSet<WitnessRef>>(),
+            )));
+        }
+
+        let proof_spec = ProofSpec {
+            statements: statements.clone(),
+            meta_statements: meta_statements.clone(),
+            context: None,
+        };
+
+        let mut witnesses = Witnesses::new();
+        witnesses.add(PoKSignatureBBSG1Wit::new_as_witness(
+            sig.clone(),
+            messages.clone().into_iter().enumerate().collect(),
+        ));
+        witnesses.add(Witness::PedersenCommitment(committed));
+
+        let proof = ProofG1::new(&mut rng, proof_spec.clone(), witnesses.clone(), None).unwrap();
+
+        verify_proof(&pvk, &snark_proof, &[min, max]).unwrap();
+        proof.verify(proof_spec, None).unwrap();
+    }
+
+    #[test]
+    fn compare_messages_sum() {
+        // Prover has 2 BBS+ signatures and he wants to prove that sum of certain signed messages from
+        // the 1st signature are less than the sum of certain signed messages from 2nd signature.
+        // This can be useful in proving sum of liabilities < sum of assets where liabilities and assets
+        // are signed under different signatures.
+        check_compare_messages_sum(&[0, 1, 2, 3], &[4, 6, 7, 8]);
+
+        // The number of messages on both sides need not be the same
+        check_compare_messages_sum(&[0, 2, 4], &[1, 3, 5, 7, 9, 11, 13]);
+        check_compare_messages_sum(
+            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            &[3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14],
+        );
+    }
+
+    #[test]
+    fn sum_bound_check_messages() {
+        // Prover has a BBS+ signature over several monthly payslips and wants to prove that the sum of some of
+        // them lies in a public range without revealing them
+
+        // 3 payslips with sum 101 + 102 + 103 = 306
+        check_sum_bound_messages(&[0, 1, 2], Fr::from(300u64), Fr::from(310u64));
+
+        // 12 payslips with sum 101 + 102 + ... + 112 = 1278
+        check_sum_bound_messages(
+            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            Fr::from(1000u64),
+            Fr::from(1278u64),
+        );
+    }
+
+    #[test]
+    fn sum_overflow() {
+        // A malicious prover uses values close to the modulus, i.e. "negative" values, to make the sum wrap
+        // around the modulus. The range checks on the summands make such a constraint system unsatisfiable.
+
+        let is_satisfied = |circuit: SumBoundCheckCircuit<Fr>| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        let bound_check = |values: Vec<Fr>, bits: usize| SumBoundCheckCircuit {
+            min: Some(Fr::from(0u64)),
+            max: Some(Fr::from(10u64)),
+            count: values.len(),
+            values: Some(values),
+            bits,
+        };
+
+        assert_eq!(sum_bit_length(8, 1), 8);
+        assert_eq!(sum_bit_length(8, 2), 9);
+        assert_eq!(sum_bit_length(8, 4), 10);
+        assert_eq!(sum_bit_length(8, 5), 11);
+
+        assert!(is_satisfied(bound_check(
+            vec![Fr::from(2u64), Fr::from(3u64), Fr::from(5u64)],
+            64
+        )));
+        // -1 + 2 + 0 = 1 wraps around the modulus
+        assert!(!is_satisfied(bound_check(
+            vec![-Fr::from(1u64), Fr::from(2u64), Fr::from(0u64)],
+            64
+        )));
+        // 2^64 - 1000 fits in 64 bits but the sum 2^64 + 5 is more than max
+        assert!(!is_satisfied(bound_check(
+            vec![
+                Fr::from(u64::MAX) - Fr::from(999u64),
+                Fr::from(1005u64),
+                Fr::from(0u64)
+            ],
+            64
+        )));
+        // Value does not fit in given bits
+        assert!(!is_satisfied(bound_check(
+            vec![Fr::from(256u64), -Fr::from(250u64)],
+            8
+        )));
+
+        let compare = |smalls: Vec<Fr>, larges: Vec<Fr>| {
+            let circuit = SumCompareCircuit {
+                smalls_count: smalls.len(),
+                larges_count: larges.len(),
+                smalls: Some(smalls),
+                larges: Some(larges),
+                bits: 64,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        assert!(compare(
+            vec![Fr::from(1000u64), Fr::from(10u64)],
+            vec![Fr::from(1011u64)]
+        ));
+        // 1000 - 990 = 10 < 20 if the sum wrapped around
+        assert!(!compare(
+            vec![Fr::from(1000u64), -Fr::from(990u64)],
+            vec![Fr::from(20u64)]
+        ));
+        assert!(!compare(
+            vec![Fr::from(1000u64), Fr::from(10u64)],
+            vec![Fr::from(1010u64)]
+        ));
+    }
+
+    #[test]
+    fn linear_combination_bound_check_messages() {
+        // Prover has 2 BBS+ signatures, each with an income, and wants to prove that 2 times the income from the
+        // 1st signature plus the income from the 2nd signature is at least a public amount. The coefficients are
+        // public so the verifier knows the formula.
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        // Income is the message at index 2 of the 1st credential, 103, and index 5 of the 2nd, 106
+        let credentials = [
+            credential(&mut rng, (101..=105u64).map(Fr::from).collect()),
+            credential(&mut rng, (101..=108u64).map(Fr::from).collect()),
+        ];
+        let values = vec![Fr::from(103u64), Fr::from(106u64)];
+        let coefficients = vec![Fr::from(2u64), Fr::from(1u64)];
+
+        let bits = 64;
+        let coefficient_bits = 8;
+        // 2 * 103 + 1 * 106 = 312
+        let min = Fr::from(300u64);
+        let max = Fr::from(u64::MAX);
+        let linear_combination = |coefficients: Vec<Fr>| Predicate::LinearCombinationBound {
+            messages: vec![(0, 2), (1, 5)],
+            coefficients,
+            min,
+            max,
+            bits,
+            coefficient_bits,
+        };
+
+        let predicates = [linear_combination(coefficients.clone())];
+        let (presentation, vks) = prove_predicates(&mut rng, &credentials, &predicates).unwrap();
+        verify_predicates(&credentials, &predicates, &vks, &presentation).unwrap();
+
+        // Verifier expecting different coefficients rejects
+        let swapped = [linear_combination(vec![coefficients[1], coefficients[0]])];
+        assert!(matches!(
+            verify_predicates(&credentials, &swapped, &vks, &presentation),
+            Err(PredicateError::PresentationMismatch)
+        ));
+
+        let is_satisfied = |coefficients: Vec<Fr>, min: Fr| {
+            let circuit = LinearCombinationBoundCircuit {
+                min: Some(min),
+                max: Some(max),
+                count: 2,
+                coefficients: Some(coefficients),
+                values: Some(values.clone()),
+                bits,
+                coefficient_bits,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+        // Exactly at min
+        assert!(is_satisfied(coefficients.clone(), Fr::from(312u64)));
+        assert!(!is_satisfied(coefficients.clone(), Fr::from(313u64)));
+        // 1 * 103 + 1 * 106 = 209
+        assert!(!is_satisfied(vec![Fr::from(1u64), Fr::from(1u64)], min));
+        // Coefficients must fit in `coefficient_bits`
+        assert!(!is_satisfied(vec![Fr::from(256u64), Fr::from(1u64)], min));
+        // 4 * 103 - 1 * 106 = 306 would be more than min if the coefficient could be negative
+        assert!(!is_satisfied(vec![Fr::from(4u64), -Fr::from(1u64)], min));
+    }
+
+    #[test]
+    fn check_witness_messages() {
+        let message = |result: Result<(), PredicateError>| match result {
+            Ok(()) => None,
+            Err(PredicateError::UnsatisfiedWitness(e)) => Some(e),
+            Err(e) => panic!("unexpected {:?}", e),
+        };
+        let frs = |vals: &[u64]| vals.iter().map(|v| Fr::from(*v)).collect::<Vec<_>>();
+
+        let bound_check = |values: &[u64], bits: usize| {
+            let circuit = SumBoundCheckCircuit {
+                min: Some(Fr::from(300u64)),
+                max: Some(Fr::from(310u64)),
+                count: values.len(),
+                values: Some(frs(values)),
+                bits,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone()).unwrap();
+            let result = circuit.check_witness();
+            assert_eq!(result.is_ok(), cs.is_satisfied().unwrap());
+            message(result)
+        };
+        assert_eq!(bound_check(&[100, 205], 16), None);
+        assert_eq!(
+            bound_check(&[100, 211], 16).as_deref(),
+            Some("sum 311 is not <= max 310")
+        );
+        assert_eq!(
+            bound_check(&[100, 199], 16).as_deref(),
+            Some("sum 299 is not >= min 300")
+        );
+        assert_eq!(
+            bound_check(&[100, 70000], 16).as_deref(),
+            Some("values[1] 70000 does not fit in 16 bits")
+        );
+        assert_eq!(
+            bound_check(&[10, 5], 4).as_deref(),
+            Some("min 300 does not fit in 5 bits")
+        );
+
+        let compare = |smalls: &[u64], larges: &[u64]| {
+            let circuit = SumCompareCircuit {
+                smalls_count: smalls.len(),
+                larges_count: larges.len(),
+                smalls: Some(frs(smalls)),
+                larges: Some(frs(larges)),
+                bits: 16,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone()).unwrap();
+            let result = circuit.check_witness();
+            assert_eq!(result.is_ok(), cs.is_satisfied().unwrap());
+            message(result)
+        };
+        assert_eq!(compare(&[1000, 10], &[1011]), None);
+        assert_eq!(
+            compare(&[1000, 10], &[1010]).as_deref(),
+            Some("sum of smalls 1010 is not < sum of larges 1010")
+        );
+
+        let linear_combination = |coefficients: &[u64], values: &[u64]| {
+            let circuit = LinearCombinationBoundCircuit {
+                min: Some(Fr::from(300u64)),
+                max: Some(Fr::from(310u64)),
+                count: values.len(),
+                coefficients: Some(frs(coefficients)),
+                values: Some(frs(values)),
+                bits: 16,
+                coefficient_bits: 4,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone()).unwrap();
+            let result = circuit.check_witness();
+            assert_eq!(result.is_ok(), cs.is_satisfied().unwrap());
+            message(result)
+        };
+        assert_eq!(linear_combination(&[2, 1], &[100, 105]), None);
+        assert_eq!(
+            linear_combination(&[2, 1], &[100, 111]).as_deref(),
+            Some("sum 311 is not <= max 310")
+        );
+
+        // Coefficients too large or negative are rejected as such rather than as an unsatisfied witness
+        for coefficients in [
+            vec![Fr::from(2u64), Fr::from(16u64)],
+            vec![Fr::from(2u64), -Fr::from(1u64)],
+        ] {
+            let circuit = LinearCombinationBoundCircuit {
+                min: Some(Fr::from(300u64)),
+                max: Some(Fr::from(310u64)),
+                count: 2,
+                coefficients: Some(coefficients),
+                values: Some(frs(&[100, 105])),
+                bits: 16,
+                coefficient_bits: 4,
+            };
+            assert!(matches!(
+                circuit.check_witness(),
+                Err(PredicateError::CoefficientOutOfRange { index: 1, bits: 4 })
+            ));
+        }
+
+        let missing = SumBoundCheckCircuit::<Fr> {
+            min: Some(Fr::from(300u64)),
+            max: Some(Fr::from(310u64)),
+            count: 2,
+            values: None,
+            bits: 16,
+        };
+        assert!(matches!(
+            missing.check_witness(),
+            Err(PredicateError::MissingAssignment)
+        ));
+        let missing = SumBoundCheckCircuit {
+            values: Some(frs(&[100])),
+            ..missing
+        };
+        assert!(matches!(
+            missing.check_witness(),
+            Err(PredicateError::CommitmentSizeMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn values_count_mismatch() {
+        // Circuits built with a number of values other than their count fail rather than panic
+        let frs = |vals: &[u64]| vals.iter().map(|v| Fr::from(*v)).collect::<Vec<_>>();
+        let is_unsatisfiable = |result: Result<(), SynthesisError>| {
+            matches!(result, Err(SynthesisError::Unsatisfiable))
+        };
+
+        let bound_check = SumBoundCheckCircuit {
+            min: Some(Fr::from(300u64)),
+            max: Some(Fr::from(310u64)),
+            count: 3,
+            values: Some(frs(&[100, 205])),
+            bits: 16,
+        };
+        assert!(is_unsatisfiable(
+            bound_check.generate_constraints(ConstraintSystem::<Fr>::new_ref())
+        ));
+
+        for (smalls_count, larges_count) in [(1, 1), (2, 2), (3, 1)] {
+            let compare = SumCompareCircuit {
+                smalls_count,
+                larges_count,
+                smalls: Some(frs(&[1000, 10])),
+                larges: Some(frs(&[1011])),
+                bits: 16,
+            };
+            assert!(is_unsatisfiable(
+                compare.generate_constraints(ConstraintSystem::<Fr>::new_ref())
+            ));
+        }
+
+        let linear_combination = LinearCombinationBoundCircuit {
+            min: Some(Fr::from(300u64)),
+            max: Some(Fr::from(310u64)),
+            count: 2,
+            coefficients: Some(frs(&[2])),
+            values: Some(frs(&[100, 105])),
+            bits: 16,
+            coefficient_bits: 4,
+        };
+        assert!(is_unsatisfiable(
+            linear_combination.generate_constraints(ConstraintSystem::<Fr>::new_ref())
+        ));
+    }
+
+    #[test]
+    fn sum_bits_boundary() {
+        // The bit-size of the sums, not only of each value, must be comparable, i.e. at most `max_cmp_bits`
+        let max = max_cmp_bits::<Fr>();
+        let is_supported = |result: Result<(), SynthesisError>| match result {
+            Ok(()) => true,
+            Err(SynthesisError::Unsatisfiable) => false,
+            Err(e) => panic!("unexpected {:?}", e),
+        };
+        let is_checked = |result: Result<(), PredicateError>| match result {
+            Ok(()) => true,
+            Err(PredicateError::UnsupportedBitSize { bits, max: m })
+                if bits == max + 1 && m == max =>
+            {
+                false
+            }
+            Err(e) => panic!("unexpected {:?}", e),
+        };
+
+        // Sum of 2 values has 1 more bit than the values
+        for (bits, supported) in [(max - 1, true), (max, false)] {
+            let circuit = SumBoundCheckCircuit {
+                min: Some(Fr::from(3u64)),
+                max: Some(Fr::from(3u64)),
+                count: 2,
+                values: Some(vec![Fr::from(1u64), Fr::from(2u64)]),
+                bits,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            assert_eq!(
+                is_supported(circuit.clone().generate_constraints(cs.clone())),
+                supported
+            );
+            assert_eq!(is_checked(circuit.check_witness()), supported);
+            if supported {
+                assert!(cs.is_satisfied().unwrap());
+            }
+
+            // The wider of the 2 sums counts
+            let circuit = SumCompareCircuit {
+                smalls_count: 1,
+                larges_count: 2,
+                smalls: Some(vec![Fr::from(1u64)]),
+                larges: Some(vec![Fr::from(1u64), Fr::from(2u64)]),
+                bits,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            assert_eq!(
+                is_supported(circuit.clone().generate_constraints(cs.clone())),
+                supported
+            );
+            assert_eq!(is_checked(circuit.check_witness()), supported);
+            if supported {
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+
+        // Each term has as many bits as its value and coefficient together
+        for (coefficient_bits, supported) in [(2, true), (3, false)] {
+            let circuit = LinearCombinationBoundCircuit {
+                min: Some(Fr::from(5u64)),
+                max: Some(Fr::from(5u64)),
+                count: 2,
+                coefficients: Some(vec![Fr::from(1u64), Fr::from(2u64)]),
+                values: Some(vec![Fr::from(1u64), Fr::from(2u64)]),
+                bits: max - 3,
+                coefficient_bits,
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            assert_eq!(
+                is_supported(circuit.clone().generate_constraints(cs.clone())),
+                supported
+            );
+            assert_eq!(is_checked(circuit.check_witness()), supported);
+            if supported {
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+
+        // Many summands add to the bit-size even when each value is small
+        let circuit = SumBoundCheckCircuit::<Fr> {
+            min: None,
+            max: None,
+            count: 1 << 20,
+            values: None,
+            bits: max - 19,
+        };
+        assert!(!is_supported(
+            circuit.generate_constraints(ConstraintSystem::<Fr>::new_ref())
+        ));
+        assert_eq!(sum_bit_length(usize::MAX, 2), usize::MAX);
+    }
 }
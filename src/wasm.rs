@@ -0,0 +1,278 @@
+use crate::bounds::BoundMode;
+use crate::error::PredicateError;
+use crate::presentation::{
+    Credential, MessageRef, Predicate, PredicateProver, PredicateVerifier, Presentation,
+};
+use crate::Fr;
+use ark_bls12_381::Bls12_381;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use bbs_plus::prelude::{PublicKeyG2, SignatureParamsG1};
+use legogroth16::{ProvingKey, VerifyingKey};
+use wasm_bindgen::prelude::*;
+
+// NOTE: All values crossing the boundary are byte arrays of the compressed `CanonicalSerialize` encoding:
+// credentials as `Vec<Credential>`, issuers as `Vec<(SignatureParamsG1, PublicKeyG2)>` in the order of the
+// credentials, LegoGroth16 keys and presentations. Errors are thrown as strings describing the `PredicateError`.
+// Build with `--no-default-features --features wasm` as the `parallel` feature needs threads.
+
+impl From<PredicateError> for JsValue {
+    fn from(e: PredicateError) -> Self {
+        JsValue::from_str(&e.to_string())
+    }
+}
+
+/// `min < message < max`, where each bound can be inclusive, over message `message` of credential `credential`
+#[wasm_bindgen]
+pub struct BoundCheck {
+    predicate: Predicate,
+}
+
+/// `min <= sum of messages <= max` over messages of any of the credentials
+#[wasm_bindgen]
+pub struct SumBound {
+    predicate: Predicate,
+}
+
+#[wasm_bindgen]
+impl BoundCheck {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        credential: usize,
+        message: usize,
+        min: u64,
+        max: u64,
+        bits: Option<usize>,
+        min_inclusive: bool,
+        max_inclusive: bool,
+    ) -> BoundCheck {
+        BoundCheck {
+            predicate: Predicate::Bound {
+                message: (credential, message),
+                min: Fr::from(min),
+                max: Fr::from(max),
+                bits,
+                mode: BoundMode::both(min_inclusive, max_inclusive),
+            },
+        }
+    }
+
+    /// Serialized presentation proving the predicate over the serialized credentials, bound to the verifier's nonce
+    pub fn prove(
+        &self,
+        credentials: &[u8],
+        proving_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, JsValue> {
+        prove(&self.predicate, credentials, proving_key, nonce)
+    }
+
+    /// Throws unless the serialized presentation proves the predicate over credentials of the issuers and was
+    /// created with the given nonce
+    pub fn verify(
+        &self,
+        presentation: &[u8],
+        issuers: &[u8],
+        verifying_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<(), JsValue> {
+        verify(&self.predicate, presentation, issuers, verifying_key, nonce)
+    }
+}
+
+#[wasm_bindgen]
+impl SumBound {
+    /// Message `message_indices[i]` of credential `credential_indices[i]` is the `i`th summand
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        credential_indices: &[u32],
+        message_indices: &[u32],
+        min: u64,
+        max: u64,
+        bits: usize,
+    ) -> Result<SumBound, JsValue> {
+        if credential_indices.len() != message_indices.len() {
+            return Err(JsValue::from_str(
+                "credential and message indices differ in length",
+            ));
+        }
+        let messages = credential_indices
+            .iter()
+            .zip(message_indices.iter())
+            .map(|(c, m)| (*c as usize, *m as usize))
+            .collect::<Vec<MessageRef>>();
+        Ok(SumBound {
+            predicate: Predicate::SumBound {
+                messages,
+                min: Fr::from(min),
+                max: Fr::from(max),
+                bits,
+            },
+        })
+    }
+
+    /// Serialized presentation proving the predicate over the serialized credentials, bound to the verifier's nonce
+    pub fn prove(
+        &self,
+        credentials: &[u8],
+        proving_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, JsValue> {
+        prove(&self.predicate, credentials, proving_key, nonce)
+    }
+
+    /// Throws unless the serialized presentation proves the predicate over credentials of the issuers and was
+    /// created with the given nonce
+    pub fn verify(
+        &self,
+        presentation: &[u8],
+        issuers: &[u8],
+        verifying_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<(), JsValue> {
+        verify(&self.predicate, presentation, issuers, verifying_key, nonce)
+    }
+}
+
+fn prove(
+    predicate: &Predicate,
+    credentials: &[u8],
+    proving_key: &[u8],
+    nonce: &[u8],
+) -> Result<Vec<u8>, JsValue> {
+    let credentials = Vec::<Credential>::deserialize(credentials).map_err(PredicateError::from)?;
+    let proving_key =
+        ProvingKey::<Bls12_381>::deserialize(proving_key).map_err(PredicateError::from)?;
+    // The browser's CSPRNG through `getrandom`
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let mut prover = PredicateProver::new(&credentials);
+    prover.add_predicate(predicate.clone(), &proving_key);
+    let presentation = prover.prove(&mut StdRng::from_seed(seed), nonce)?;
+    let mut bytes = vec![];
+    presentation
+        .serialize(&mut bytes)
+        .map_err(PredicateError::from)?;
+    Ok(bytes)
+}
+
+fn verify(
+    predicate: &Predicate,
+    presentation: &[u8],
+    issuers: &[u8],
+    verifying_key: &[u8],
+    nonce: &[u8],
+) -> Result<(), JsValue> {
+    let presentation = Presentation::deserialize(presentation).map_err(PredicateError::from)?;
+    let issuers =
+        Vec::<(SignatureParamsG1<Bls12_381>, PublicKeyG2<Bls12_381>)>::deserialize(issuers)
+            .map_err(PredicateError::from)?;
+    let verifying_key =
+        VerifyingKey::<Bls12_381>::deserialize(verifying_key).map_err(PredicateError::from)?;
+    let mut verifier = PredicateVerifier::new(issuers);
+    verifier.add_predicate(predicate.clone(), &verifying_key);
+    Ok(verifier.verify(&presentation, nonce)?)
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use wasm_bindgen_test::*;
+
+    fn to_bytes<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+        let mut bytes = vec![];
+        value.serialize(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Serialized credentials and issuers for signatures over the given messages
+    fn credentials(messages: Vec<Vec<u64>>) -> (Vec<u8>, Vec<u8>) {
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let mut credentials = vec![];
+        let mut issuers = vec![];
+        for messages in messages {
+            let messages = messages.into_iter().map(Fr::from).collect();
+            let (messages, params, keypair, signature) =
+                sig_setup_with_messages(&mut rng, messages);
+            issuers.push((params.clone(), keypair.public_key.clone()));
+            credentials.push(Credential {
+                signature,
+                messages,
+                params,
+                public_key: keypair.public_key,
+            });
+        }
+        (to_bytes(&credentials), to_bytes(&issuers))
+    }
+
+    /// Serialized proving and verifying keys of the predicate's circuit
+    fn keys(predicate: &Predicate) -> (Vec<u8>, Vec<u8>) {
+        let mut rng = StdRng::seed_from_u64(1u64);
+        let pk = predicate.generate_proving_key(&mut rng).unwrap();
+        (to_bytes(&pk), to_bytes(&pk.vk))
+    }
+
+    #[wasm_bindgen_test]
+    fn bound_check_presentation() {
+        let (credentials, issuers) = credentials(vec![vec![1, 105, 3]]);
+        let bound = BoundCheck::new(0, 1, 100, 110, Some(16), false, true);
+        let (pk, vk) = keys(&bound.predicate);
+
+        let presentation = bound.prove(&credentials, &pk, NONCE).unwrap();
+        bound.verify(&presentation, &issuers, &vk, NONCE).unwrap();
+        // Presentation was created for another nonce
+        assert!(bound
+            .verify(&presentation, &issuers, &vk, b"other nonce")
+            .is_err());
+
+        // Verifier expecting other bounds refuses the presentation
+        let other = BoundCheck::new(0, 1, 104, 110, Some(16), false, true);
+        assert!(other.verify(&presentation, &issuers, &vk, NONCE).is_err());
+        // Tampered presentation
+        let mut tampered = presentation;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(bound.verify(&tampered, &issuers, &vk, NONCE).is_err());
+
+        // Message is not within the bounds
+        let bound = BoundCheck::new(0, 1, 100, 105, Some(16), false, false);
+        assert!(bound.prove(&credentials, &pk, NONCE).is_err());
+        // Inclusive upper bound
+        let bound = BoundCheck::new(0, 1, 100, 105, Some(16), false, true);
+        let presentation = bound.prove(&credentials, &pk, NONCE).unwrap();
+        bound.verify(&presentation, &issuers, &vk, NONCE).unwrap();
+
+        assert!(bound.prove(&credentials[1..], &pk, NONCE).is_err());
+        assert!(bound.prove(&credentials, &vk, NONCE).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn sum_bound_presentation() {
+        let (credentials, issuers) = credentials(vec![vec![1, 2000], vec![3000, 4, 5]]);
+        let sum = SumBound::new(&[0, 1], &[1, 0], 4000, 6000, 16).unwrap();
+        let (pk, vk) = keys(&sum.predicate);
+
+        let presentation = sum.prove(&credentials, &pk, NONCE).unwrap();
+        sum.verify(&presentation, &issuers, &vk, NONCE).unwrap();
+
+        let other = SumBound::new(&[0, 1], &[1, 0], 5500, 6000, 16).unwrap();
+        assert!(other.verify(&presentation, &issuers, &vk, NONCE).is_err());
+        // Issuers in the wrong order
+        let mut reversed =
+            Vec::<(SignatureParamsG1<Bls12_381>, PublicKeyG2<Bls12_381>)>::deserialize(
+                &issuers[..],
+            )
+            .unwrap();
+        reversed.reverse();
+        assert!(sum
+            .verify(&presentation, &to_bytes(&reversed), &vk, NONCE)
+            .is_err());
+
+        // The sum 5000 is more than max
+        let sum = SumBound::new(&[0, 1], &[1, 0], 4000, 4500, 16).unwrap();
+        assert!(sum.prove(&credentials, &pk, NONCE).is_err());
+
+        assert!(SumBound::new(&[0, 1], &[1], 4000, 6000, 16).is_err());
+    }
+}
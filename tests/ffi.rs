@@ -0,0 +1,149 @@
+//! Builds the library as a C shared library, builds `tests/ffi/harness.c` against it and the committed header and
+//! runs it over presentations created here. Needs a C compiler, `cc` unless set by `CC`. Also checks that the
+//! committed header is up to date.
+#![cfg(all(feature = "ffi", unix))]
+
+use ark_bls12_381::Bls12_381;
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use bbs_plus::prelude::{KeypairG2, SignatureG1, SignatureParamsG1};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use test_bbs_snark::bounds::BoundMode;
+use test_bbs_snark::presentation::{Credential, Predicate, PredicateProver};
+use test_bbs_snark::Fr;
+
+/// Nonce the presentations are created with, written as `nonce` for the harness
+const NONCE: &[u8] = b"verifier nonce";
+
+fn write<T: CanonicalSerialize>(dir: &Path, name: &str, value: &T) {
+    let mut bytes = vec![];
+    value.serialize(&mut bytes).unwrap();
+    fs::write(dir.join(name), bytes).unwrap();
+}
+
+fn credential<R: RngCore>(rng: &mut R, messages: &[u64]) -> Credential {
+    let messages = messages.iter().map(|m| Fr::from(*m)).collect::<Vec<_>>();
+    let params = SignatureParamsG1::<Bls12_381>::generate_using_rng(rng, messages.len());
+    let keypair = KeypairG2::<Bls12_381>::generate_using_rng(rng, &params);
+    let signature =
+        SignatureG1::<Bls12_381>::new(rng, &messages, &keypair.secret_key, &params).unwrap();
+    Credential {
+        signature,
+        messages,
+        params,
+        public_key: keypair.public_key,
+    }
+}
+
+/// Write the verifying key and a presentation of `predicate` over `credentials` as `<name>.vk` and
+/// `<name>.presentation`
+fn write_presentation<R: RngCore>(
+    rng: &mut R,
+    dir: &Path,
+    name: &str,
+    credentials: &[Credential],
+    predicate: Predicate,
+) {
+    let pk = predicate.generate_proving_key(rng).unwrap();
+    let mut prover = PredicateProver::new(credentials);
+    prover.add_predicate(predicate, &pk);
+    write(dir, &format!("{}.vk", name), &pk.vk);
+    write(
+        dir,
+        &format!("{}.presentation", name),
+        &prover.prove(rng, NONCE).unwrap(),
+    );
+}
+
+/// Build the library as a cdylib, which the crate is not built as by default, and return the directory with it.
+/// It is built with `cargo rustc --crate-type cdylib` in its own target directory under `CARGO_TARGET_TMPDIR`,
+/// so it does not depend on the layout of the target directory of the tests.
+fn build_library() -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "ffi",
+            "--crate-type",
+            "cdylib",
+        ])
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "building the library failed");
+    target_dir.join("debug")
+}
+
+#[test]
+fn verify_from_c() {
+    let mut rng = StdRng::seed_from_u64(0u64);
+    let dir = std::env::temp_dir().join(format!("bbs-predicate-ffi-{}", rng.next_u64()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("nonce"), NONCE).unwrap();
+
+    let credentials = [
+        credential(&mut rng, &[1, 105, 3]),
+        credential(&mut rng, &[100, 7, 20]),
+    ];
+    for (i, c) in credentials.iter().enumerate() {
+        write(&dir, &format!("issuer{}.params", i), &c.params);
+        write(&dir, &format!("issuer{}.pk", i), &c.public_key);
+    }
+    let bound_check = Predicate::Bound {
+        message: (0, 1),
+        min: Fr::from(100u64),
+        max: Fr::from(110u64),
+        bits: Some(16),
+        mode: BoundMode::both(false, true),
+    };
+    write_presentation(&mut rng, &dir, "bound_check", &credentials, bound_check);
+    let sum_compare = Predicate::SumCompare {
+        smalls: vec![(0, 1)],
+        larges: vec![(1, 0), (1, 2)],
+        bits: 16,
+    };
+    write_presentation(&mut rng, &dir, "sum_compare", &credentials, sum_compare);
+
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let library_dir = build_library();
+    let harness = dir.join("harness");
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg(manifest_dir.join("tests/ffi/harness.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(&library_dir)
+        .arg(format!("-Wl,-rpath,{}", library_dir.display()))
+        .args(["-ltest_bbs_snark", "-o"])
+        .arg(&harness)
+        .status()
+        .unwrap();
+    assert!(status.success(), "compiling the harness failed");
+
+    let output = Command::new(&harness).arg(&dir).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The committed header is the one `build.rs` generates from the current `src/ffi.rs`
+#[test]
+fn header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/bbs_predicate.h"));
+    assert!(
+        generated == include_str!("../include/bbs_predicate.h"),
+        "include/bbs_predicate.h is outdated, replace it with {}/bbs_predicate.h",
+        env!("OUT_DIR")
+    );
+}
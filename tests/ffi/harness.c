@@ -0,0 +1,147 @@
+/* Verifies the presentations written by `tests/ffi.rs` in the directory given as argument through the C API */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "bbs_predicate.h"
+
+static int failures = 0;
+
+#define CHECK(call, expected) check(#call, (call), (expected))
+/* For failures whose code depends on where the verification stops */
+#define CHECK_FAILS(call) check_fails(#call, (call))
+
+static void check(const char *call, BbsStatus status, BbsStatus expected) {
+  if (status != expected) {
+    fprintf(stderr, "%s returned %d instead of %d\n", call, status, expected);
+    failures++;
+  }
+}
+
+static void check_fails(const char *call, BbsStatus status) {
+  if (status == BBS_STATUS_OK || status == BBS_STATUS_PANIC) {
+    fprintf(stderr, "%s returned %d instead of an error\n", call, status);
+    failures++;
+  }
+}
+
+/* Content of file `name` in `dir`, exits if it can't be read */
+static uint8_t *read_file(const char *dir, const char *name, size_t *len) {
+  char path[4096];
+  snprintf(path, sizeof(path), "%s/%s", dir, name);
+  FILE *file = fopen(path, "rb");
+  if (file == NULL) {
+    perror(path);
+    exit(2);
+  }
+  fseek(file, 0, SEEK_END);
+  *len = (size_t)ftell(file);
+  fseek(file, 0, SEEK_SET);
+  uint8_t *bytes = malloc(*len);
+  if (bytes == NULL || fread(bytes, 1, *len, file) != *len) {
+    perror(path);
+    exit(2);
+  }
+  fclose(file);
+  return bytes;
+}
+
+static BbsVerifyingKey *verifying_key(const char *dir, const char *name) {
+  size_t len;
+  uint8_t *bytes = read_file(dir, name, &len);
+  BbsVerifyingKey *key = NULL;
+  CHECK(bbs_verifying_key_from_bytes(bytes, len, &key), BBS_STATUS_OK);
+  free(bytes);
+  return key;
+}
+
+static BbsPublicKey *public_key(const char *dir, const char *params_name, const char *public_key_name) {
+  size_t params_len, public_key_len;
+  uint8_t *params = read_file(dir, params_name, &params_len);
+  uint8_t *public_key = read_file(dir, public_key_name, &public_key_len);
+  BbsPublicKey *key = NULL;
+  CHECK(bbs_public_key_from_bytes(params, params_len, public_key, public_key_len, &key), BBS_STATUS_OK);
+  free(params);
+  free(public_key);
+  return key;
+}
+
+static BbsPresentation *presentation(const char *dir, const char *name) {
+  size_t len;
+  uint8_t *bytes = read_file(dir, name, &len);
+  BbsPresentation *presentation = NULL;
+  CHECK(bbs_presentation_from_bytes(bytes, len, &presentation), BBS_STATUS_OK);
+  free(bytes);
+  return presentation;
+}
+
+int main(int argc, char **argv) {
+  if (argc != 2) {
+    fprintf(stderr, "usage: %s <fixtures directory>\n", argv[0]);
+    return 2;
+  }
+  const char *dir = argv[1];
+
+  BbsVerifyingKey *bound_vk = verifying_key(dir, "bound_check.vk");
+  BbsVerifyingKey *sum_vk = verifying_key(dir, "sum_compare.vk");
+  const BbsPublicKey *issuers[2] = {public_key(dir, "issuer0.params", "issuer0.pk"),
+                                    public_key(dir, "issuer1.params", "issuer1.pk")};
+  const BbsPublicKey *reversed[2] = {issuers[1], issuers[0]};
+  BbsPresentation *bound = presentation(dir, "bound_check.presentation");
+  BbsPresentation *sum = presentation(dir, "sum_compare.presentation");
+  size_t nonce_len;
+  uint8_t *nonce = read_file(dir, "nonce", &nonce_len);
+  uint8_t other_nonce[] = {'o', 't', 'h', 'e', 'r'};
+
+  /* 100 < message 1 of credential 0 <= 110 */
+  BbsBoundCheck bound_check = {{0, 1}, 100, 110, 16, false, true};
+  CHECK(bbs_verify_bound_check(bound, issuers, 2, bound_vk, &bound_check, nonce, nonce_len), BBS_STATUS_OK);
+  BbsBoundCheck other_bound_check = bound_check;
+  other_bound_check.max = 120;
+  CHECK(bbs_verify_bound_check(bound, issuers, 2, bound_vk, &other_bound_check, nonce, nonce_len),
+        BBS_STATUS_PRESENTATION_MISMATCH);
+  CHECK_FAILS(bbs_verify_bound_check(bound, reversed, 2, bound_vk, &bound_check, nonce, nonce_len));
+  /* Presentation was created for another nonce */
+  CHECK_FAILS(
+      bbs_verify_bound_check(bound, issuers, 2, bound_vk, &bound_check, other_nonce, sizeof(other_nonce)));
+  CHECK(bbs_verify_bound_check(bound, issuers, 2, bound_vk, &bound_check, NULL, nonce_len),
+        BBS_STATUS_NULL_POINTER);
+  CHECK(bbs_verify_bound_check(bound, issuers, 2, bound_vk, NULL, nonce, nonce_len), BBS_STATUS_NULL_POINTER);
+  CHECK(bbs_verify_bound_check(bound, NULL, 2, bound_vk, &bound_check, nonce, nonce_len), BBS_STATUS_NULL_POINTER);
+
+  /* Message 1 of credential 0 < messages 0 and 2 of credential 1 */
+  BbsMessageRef smalls[1] = {{0, 1}};
+  BbsMessageRef larges[2] = {{1, 0}, {1, 2}};
+  BbsSumCompare sum_compare = {smalls, 1, larges, 2, 16};
+  CHECK(bbs_verify_sum_compare(sum, issuers, 2, sum_vk, &sum_compare, nonce, nonce_len), BBS_STATUS_OK);
+  /* The bound check's verifying key is of another circuit */
+  CHECK_FAILS(bbs_verify_sum_compare(sum, issuers, 2, bound_vk, &sum_compare, nonce, nonce_len));
+  CHECK(bbs_verify_sum_compare(bound, issuers, 2, sum_vk, &sum_compare, nonce, nonce_len),
+        BBS_STATUS_PRESENTATION_MISMATCH);
+
+  uint8_t garbage[8] = {1, 2, 3, 4, 5, 6, 7, 8};
+  BbsPresentation *invalid = NULL;
+  CHECK(bbs_presentation_from_bytes(garbage, sizeof(garbage), &invalid), BBS_STATUS_SERIALIZATION);
+  CHECK(bbs_presentation_from_bytes(NULL, 8, &invalid), BBS_STATUS_NULL_POINTER);
+  CHECK(bbs_presentation_from_bytes(garbage, sizeof(garbage), NULL), BBS_STATUS_NULL_POINTER);
+  if (invalid != NULL) {
+    fprintf(stderr, "presentation created from invalid bytes\n");
+    failures++;
+  }
+
+  free(nonce);
+  bbs_presentation_free(bound);
+  bbs_presentation_free(sum);
+  bbs_presentation_free(NULL);
+  bbs_public_key_free((BbsPublicKey *)issuers[0]);
+  bbs_public_key_free((BbsPublicKey *)issuers[1]);
+  bbs_verifying_key_free(bound_vk);
+  bbs_verifying_key_free(sum_vk);
+
+  if (failures != 0) {
+    fprintf(stderr, "%d checks failed\n", failures);
+    return 1;
+  }
+  return 0;
+}